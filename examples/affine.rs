@@ -21,7 +21,6 @@ use lib::{
     base::BasePlugin,
     physic::{PhysicConfig, PhysicObject, PhysicPlugin},
     render::{AffineSprite, RepeatedSprite},
-    sprite_loader::Sprites,
 };
 
 /// Main entry point.
@@ -51,24 +50,24 @@ struct Rotate;
 #[derive(Component)]
 struct Scale;
 
-fn setup_balls(mut commands: Commands, sprites: NonSend<Sprites>) {
+fn setup_balls(mut commands: Commands) {
     commands.spawn((
         Transform::from_xyz(20., 20., 0.),
         Rotate,
         Scale,
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 
     commands.spawn((
         Transform::from_xyz(100., 20., 0.),
         Rotate,
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 
     commands.spawn((
         Transform::from_xyz(20., 100., 0.),
         Scale,
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 }
 
@@ -19,8 +19,7 @@ use bevy::prelude::*;
 use lib::{
     ball_type::{BallPlugin, EnemyBall, PlayerBall},
     base::BasePlugin,
-    physic::{PhysicConfig, PhysicObject, PhysicPlugin},
-    sprite_loader::Sprites,
+    physic::{PhysicConfig, PhysicObject, PhysicPlugin, from_vec2},
 };
 
 /// Main entry point.
@@ -42,32 +41,32 @@ pub extern "C" fn main() -> ! {
     loop {}
 }
 
-fn setup_ball(mut commands: Commands, sprites: NonSend<Sprites>) {
+fn setup_ball(mut commands: Commands) {
     commands.spawn((
         Transform::from_xyz(20., 20., 0.),
         PhysicObject {
-            impulse: Vec2::X * 100.,
+            impulse: from_vec2(Vec2::X * 100.),
             ..Default::default()
         },
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 
     commands.spawn((
         Transform::from_xyz(100., 20., 0.),
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 
     commands.spawn((
         Transform::from_xyz(20., 100., 0.),
         PhysicObject {
-            impulse: Vec2::X * 100.,
+            impulse: from_vec2(Vec2::X * 100.),
             ..Default::default()
         },
-        PlayerBall::Boy.to_bundle(&sprites, true),
+        PlayerBall::Boy.to_bundle(true),
     ));
 
     commands.spawn((
         Transform::from_xyz(100., 100., 0.),
-        EnemyBall::GreenBlob.to_bundle(&sprites),
+        EnemyBall::GreenBlob.to_bundle(),
     ));
 }
@@ -19,8 +19,7 @@ use bevy::prelude::*;
 use lib::{
     ball_type::{BallPlugin, PlayerBall},
     base::BasePlugin,
-    physic::{CircleCollider, PhysicConfig, PhysicObject, PhysicPlugin},
-    sprite_loader::Sprites,
+    physic::{CircleCollider, PhysicConfig, PhysicObject, PhysicPlugin, from_vec2},
 };
 
 /// Main entry point.
@@ -42,33 +41,33 @@ pub extern "C" fn main() -> ! {
     loop {}
 }
 
-fn setup_physic_test(mut commands: Commands, sprites: NonSend<Sprites>) {
+fn setup_physic_test(mut commands: Commands) {
     for impulse in [20., 40., 80.] {
         commands.spawn((
             Transform::from_xyz(impulse * 2., 128., 0.),
             PhysicObject {
-                impulse: Vec2::Y * -impulse,
+                impulse: from_vec2(Vec2::Y * -impulse),
                 ..default()
             },
-            PlayerBall::Princess.to_bundle(&sprites, true),
+            PlayerBall::Princess.to_bundle(true),
         ));
 
         commands.spawn((
             Transform::from_xyz(impulse * 2., 0., 0.),
             PhysicObject {
-                impulse: Vec2::Y * impulse,
+                impulse: from_vec2(Vec2::Y * impulse),
                 ..default()
             },
-            PlayerBall::Princess.to_bundle(&sprites, true),
+            PlayerBall::Princess.to_bundle(true),
         ));
 
         commands.spawn((
             Transform::from_xyz(0., impulse, 0.),
             PhysicObject {
-                impulse: Vec2::from_angle(220.) * impulse,
+                impulse: from_vec2(Vec2::from_angle(220.) * impulse),
                 ..default()
             },
-            PlayerBall::Princess.to_bundle(&sprites, true),
+            PlayerBall::Princess.to_bundle(true),
         ));
     }
 }
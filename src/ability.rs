@@ -0,0 +1,275 @@
+#[cfg(feature = "gba")]
+use agb::display::{object::PaletteVram, palette16::Palette16};
+use agb_fixnum::num;
+use bevy::prelude::*;
+
+#[cfg(feature = "gba")]
+use crate::render::{DOT_CENTER, Sprite, SpriteHandles, dot_sprite};
+use crate::{
+    ball_type::Team,
+    physic::{
+        CircleCollider, PhysicObject, Scalar, Vector, direction_and_length, perpendicular, to_vec2,
+    },
+};
+
+// Settings: Abilities.
+/// Speed added by a dash, in pixels per second.
+const DASH_SPEED: Scalar = Scalar::from_raw(120 << 8);
+/// Distance from the ball a push-wave reaches enemies, in pixels.
+const PUSH_WAVE_RADIUS: Scalar = Scalar::from_raw(40 << 8);
+/// Speed given by a push-wave to the enemies right next to the ball.
+const PUSH_WAVE_SPEED: Scalar = Scalar::from_raw(150 << 8);
+/// Speed the two halves of a split ball move apart with.
+const SPLIT_SPEED: Scalar = Scalar::from_raw(40 << 8);
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(use_ability);
+        app.add_systems(FixedUpdate, cool_down_abilities);
+
+        #[cfg(feature = "gba")]
+        {
+            app.add_observer(spawn_ability_cue);
+            app.add_systems(
+                PostUpdate,
+                update_ability_cues.before(TransformSystem::TransformPropagate),
+            );
+        }
+    }
+}
+
+/// What a player ball can do once while it is moving.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbilityKind {
+    /// Speed up along the current velocity.
+    Dash,
+    /// Push the enemies around the ball away.
+    PushWave,
+    /// Split into two smaller balls moving apart.
+    Split,
+}
+
+impl AbilityKind {
+    /// Fixed steps after the shot before the ability can be used.
+    pub fn cooldown(self) -> u8 {
+        match self {
+            Self::Dash => 8,
+            Self::PushWave => 16,
+            Self::Split => 12,
+        }
+    }
+}
+
+/// Ability of a player ball, see `PlayerBallStats::ability`. It can be used once the ball
+/// has been moving for the cooldown of the ability, by triggering `UseAbility` on it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Ability {
+    pub kind: AbilityKind,
+    /// Fixed steps left before the ability can be used.
+    pub cooldown: u8,
+    pub used: bool,
+}
+
+impl Ability {
+    pub fn new(kind: AbilityKind) -> Self {
+        Self {
+            kind,
+            cooldown: kind.cooldown(),
+            used: false,
+        }
+    }
+
+    /// Ability which can't be used anymore.
+    pub fn spent(kind: AbilityKind) -> Self {
+        Self {
+            used: true,
+            ..Self::new(kind)
+        }
+    }
+
+    pub fn is_ready(&self, physic_object: &PhysicObject) -> bool {
+        !self.used && self.cooldown == 0 && physic_object.is_awake()
+    }
+}
+
+/// Use the ability of the target ball. Nothing happens if it isn't ready.
+#[derive(Event)]
+pub struct UseAbility;
+
+fn cool_down_abilities(mut abilities: Query<(&mut Ability, &PhysicObject)>) {
+    for (mut ability, physic_object) in &mut abilities {
+        // The cooldown starts when the ball is shot
+        if physic_object.enable && ability.cooldown > 0 {
+            ability.cooldown -= 1;
+        }
+    }
+}
+
+fn use_ability(
+    trigger: Trigger<UseAbility>,
+    mut commands: Commands,
+    mut balls: Query<(
+        &mut Ability,
+        &mut PhysicObject,
+        &CircleCollider,
+        &Team,
+        &Transform,
+    )>,
+    mut others: Query<(&mut PhysicObject, &CircleCollider, &Team), Without<Ability>>,
+) {
+    let ball = trigger.target();
+    let Ok((mut ability, mut physic_object, collider, team, transform)) = balls.get_mut(ball)
+    else {
+        return;
+    };
+
+    if !ability.is_ready(&physic_object) {
+        return;
+    }
+    ability.used = true;
+
+    let velocity = physic_object.velocity;
+    let direction = direction_and_length(velocity).map_or(
+        Vector::new(Scalar::new(0), Scalar::new(-1)),
+        |(direction, _)| direction,
+    );
+    let center = collider.center(&physic_object);
+
+    match ability.kind {
+        AbilityKind::Dash => {
+            let mass = physic_object.mass;
+            physic_object.impulse += direction * DASH_SPEED * mass;
+        }
+        AbilityKind::PushWave => {
+            for (mut other, other_collider, other_team) in &mut others {
+                if !other_team.is_enemy() || !other.enable || !other.is_dynamic() {
+                    continue;
+                }
+
+                let offset = other_collider.center(&other) - center;
+                let Some((away, distance)) = direction_and_length(offset) else {
+                    continue;
+                };
+                let reach = PUSH_WAVE_RADIUS + other_collider.radius();
+                if distance >= reach {
+                    continue;
+                }
+
+                // Weaker the further the enemy is
+                let speed = PUSH_WAVE_SPEED * (reach - distance) / reach;
+                let mass = other.mass;
+                other.impulse += away * speed * mass;
+            }
+        }
+        AbilityKind::Split => {
+            let Team::Player(player_ball) = *team else {
+                return;
+            };
+
+            let normal = perpendicular(direction);
+            for side in [normal, -normal] {
+                let half_collider = player_ball.half_collider();
+                let half_center = center + side * half_collider.radius();
+                let origin = half_collider.origin_for_center(half_center);
+
+                commands.spawn((
+                    Transform::from_translation(to_vec2(origin).extend(transform.translation.z)),
+                    player_ball.half_bundle(PhysicObject {
+                        velocity: velocity + side * SPLIT_SPEED,
+                        mass: physic_object.mass * num!(0.5),
+                        // Computed again for the smaller collider
+                        inertia: Scalar::new(0),
+                        ..physic_object.clone()
+                    }),
+                ));
+            }
+
+            commands.entity(ball).despawn();
+        }
+    }
+}
+
+/// Blinking dot above a ball while its ability is ready.
+#[cfg(feature = "gba")]
+#[derive(Component)]
+struct AbilityCue {
+    ball: Entity,
+}
+
+/// Gap between the top of the ball and its cue, in pixels.
+#[cfg(feature = "gba")]
+const CUE_GAP: f32 = 3.0;
+/// Times per second the cue blinks.
+#[cfg(feature = "gba")]
+const CUE_BLINK_RATE: f32 = 4.0;
+
+#[cfg(feature = "gba")]
+fn spawn_ability_cue(
+    trigger: Trigger<OnInsert, Ability>,
+    mut commands: Commands,
+    abilities: Query<&Ability>,
+    cues: Query<(Entity, &AbilityCue)>,
+    mut handles: NonSendMut<SpriteHandles>,
+    mut cue_sprite: Local<Option<Sprite>>,
+) {
+    // Inserted again, like by a pickup, the ball keeps a single cue
+    for (entity, cue) in &cues {
+        if cue.ball == trigger.target() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if abilities
+        .get(trigger.target())
+        .is_ok_and(|ability| ability.used)
+    {
+        return;
+    }
+
+    let sprite = cue_sprite.get_or_insert_with(|| {
+        let mut palette = [0x0; 16];
+        palette[1] = 0x03FF; // Yellow
+        let palette = PaletteVram::new(&Palette16::new(palette)).unwrap();
+
+        let mut sprite = dot_sprite(&mut handles, &palette, 1);
+        sprite.visible = false;
+        sprite
+    });
+
+    commands.spawn((
+        AbilityCue {
+            ball: trigger.target(),
+        },
+        Transform::IDENTITY,
+        sprite.clone(),
+    ));
+}
+
+#[cfg(feature = "gba")]
+fn update_ability_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    balls: Query<(&Ability, &PhysicObject, &CircleCollider)>,
+    mut cues: Query<(Entity, &AbilityCue, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, cue, mut transform, mut sprite) in &mut cues {
+        let Ok((ability, physic_object, collider)) = balls.get(cue.ball) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        if ability.used {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let blink = (time.elapsed_secs() * CUE_BLINK_RATE * 2.0) as u32 % 2 == 0;
+        sprite.visible = ability.is_ready(physic_object) && blink;
+
+        let above = Vec2::new(0.0, -(collider.radius as f32 + CUE_GAP));
+        let center = to_vec2(collider.center(physic_object)) + above;
+        transform.translation = (center - DOT_CENTER).extend(0.);
+    }
+}
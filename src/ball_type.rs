@@ -1,112 +1,152 @@
-use bevy::{
-    ecs::{children, component::Component},
-    prelude::*,
-    transform::components::Transform,
-};
+use agb_fixnum::num;
+use bevy::{ecs::component::Component, prelude::*, transform::components::Transform};
+#[cfg(feature = "gba")]
 use bevy_mod_gba::Sprite;
 
 use crate::{
+    ability::{Ability, AbilityKind},
+    enemy_behavior::EnemyBehavior,
     level::Level,
-    physic::{CircleCollider, Collision, PhysicObject, detect_collision},
+    on_death::{DeathEffect, OnDeath, Pickup},
+    physic::{
+        CircleCollider, CollisionLayers, CollisionStarted, CombineRule, LeftBoundary, PhysicConfig,
+        PhysicMaterial, PhysicObject, RigidBodyKind, Scalar, Vector, from_vec2, rotate, to_vec2,
+    },
+};
+#[cfg(feature = "gba")]
+use crate::{
     render::{AffineSprite, RepeatedSprite},
     sprite_loader::Sprites,
 };
 
+pub const PLAYER_LAYER: u16 = 1 << 0;
+pub const ENEMY_LAYER: u16 = 1 << 1;
+pub const SCENERY_LAYER: u16 = 1 << 2;
+
 pub struct BallPlugin;
 
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(reduce_life);
+        app.add_observer(ball_out);
+        #[cfg(feature = "gba")]
+        app.add_observer(attach_sprite);
         app.add_systems(
             PostUpdate,
-            (rotate_balls, despawn).chain().after(detect_collision),
+            despawn.before(TransformSystem::TransformPropagate),
         );
     }
 }
 
-fn reduce_life(collision: Trigger<Collision>, mut balls: Query<(&mut Life, &Team)>) {
+fn reduce_life(
+    collision: Trigger<CollisionStarted>,
+    mut balls: Query<(&mut Life, &Team, &PhysicObject)>,
+) {
     let Some((self_entity, other_entity)) = collision.self_and_other(&collision.target()) else {
         return;
     };
 
-    if balls
-        .get(self_entity)
-        .map_or(true, |(_life, team)| team.is_enemy())
-    {
+    let Ok((_life, team, attacker)) = balls.get(self_entity) else {
         return;
-    }
+    };
+    let Some(damage) = team.damage() else {
+        return;
+    };
+    let weight = attacker.mass * damage;
 
-    let Ok((mut enemy_life, Team::Enemy(_))) = balls.get_mut(other_entity) else {
+    let Ok((mut enemy_life, &Team::Enemy(enemy_ball), enemy)) = balls.get_mut(other_entity) else {
         return;
     };
 
-    if **enemy_life > 0 {
+    // The normal goes from `entity1` toward `entity2`
+    let toward_attacker = if other_entity == collision.entity1 {
+        collision.normal
+    } else {
+        -collision.normal
+    };
+    let damage = enemy_ball.damage(&Hit {
+        impact_speed: collision.impact_speed,
+        weight,
+        direction: rotate(toward_attacker, -enemy.rotation),
+    });
+
+    if **enemy_life > 0 && damage > 0 {
         // info!("collision causing reducing the life");
-        **enemy_life -= 1
+        **enemy_life = enemy_life.saturating_sub(damage);
     }
 }
 
-fn rotate_balls(balls: Query<(&PhysicObject, &mut Transform), With<Team>>) {
-    for (po, mut transform) in balls {
-        let velocity = po.velocity.length_squared();
-
-        if velocity > 5. {
-            let side = if po.velocity.x >= 0. { 1. } else { -1. };
-            let angle = side * (velocity / 10000.).clamp(0.01, 0.025);
-            transform.rotate_z(angle);
-        }
+/// Balls leaving the arena through a gap are out of the game.
+fn ball_out(trigger: Trigger<LeftBoundary>, mut balls: Query<&mut Life>) {
+    if let Ok(mut life) = balls.get_mut(trigger.target()) {
+        **life = 0;
     }
 }
 
 fn despawn(
     mut commands: Commands,
-    lifes: Query<(Entity, &Life, &Team, &GlobalTransform, &PhysicObject), Changed<Life>>,
-    sprites: NonSend<Sprites>,
+    config: Res<PhysicConfig>,
+    lifes: Query<
+        (
+            Entity,
+            &Life,
+            &PhysicObject,
+            &CircleCollider,
+            Option<&OnDeath>,
+        ),
+        Changed<Life>,
+    >,
 ) {
-    for (entity, life, team, transform, physic_object) in lifes {
+    for (entity, life, physic_object, collider, on_death) in lifes {
         if **life == 0 {
             // info!("Destroying a ball");
             commands.entity(entity).despawn();
 
-            if *team == Team::Enemy(EnemyBall::RedBlob) {
-                let length = physic_object.impulse.length();
-                let impulse = physic_object.impulse.normalize();
-                let normal = Vec2::new(impulse.y, -impulse.x);
-
-                let base = transform.translation();
-                let impulse1 = impulse + normal;
-                let pos1 = 9. * impulse1.extend(0.) + base;
-
-                // info!("Spawn green {impulse1} {pos1}");
-
-                let impulse2 = impulse - normal;
-                let pos2 = 9. * impulse2.extend(0.) + base;
-
-                commands
-                    .spawn((
-                        Transform::from_translation(pos1),
-                        EnemyBall::GreenBlob.to_bundle(&sprites),
-                    ))
-                    .insert(PhysicObject {
-                        impulse: impulse1 * length,
-                        ..EnemyBall::GreenBlob.physic_object()
-                    });
-
-                commands
-                    .spawn((
-                        Transform::from_translation(pos2),
-                        EnemyBall::GreenBlob.to_bundle(&sprites),
-                    ))
-                    .insert(PhysicObject {
-                        impulse: impulse2 * length,
-                        ..EnemyBall::GreenBlob.physic_object()
-                    });
+            // Balls falling out of the arena are gone without a trace
+            let center = collider.center(physic_object);
+            if let Some(on_death) = on_death
+                && !config.boundary.is_out(center, collider.radius())
+            {
+                // The killing hit is still an impulse, applied on the next step
+                let velocity = physic_object.velocity + physic_object.impulse / physic_object.mass;
+                on_death.apply(&mut commands, center, velocity);
             }
         }
     }
 }
 
-#[derive(Component, Clone, Copy, PartialEq)]
+/// Give a new ball its sprite, as a child placed over its collider.
+#[cfg(feature = "gba")]
+fn attach_sprite(
+    trigger: Trigger<OnAdd, Team>,
+    mut commands: Commands,
+    balls: Query<(&Team, &CircleCollider)>,
+    sprites: NonSend<Sprites>,
+) {
+    let Ok((team, collider)) = balls.get(trigger.target()) else {
+        return;
+    };
+
+    let sprite = match team {
+        Team::Player(ball) => ball.sprite(&sprites),
+        Team::Enemy(ball) => ball.sprite(&sprites),
+    };
+
+    let sprite_size = Scalar::new(team.sprite_size() as i32);
+    let sprite_translation = collider.sprite_translation(Vector::new(sprite_size, sprite_size));
+    // Balls smaller than usual, like split halves, get a shrunk sprite
+    let scale = collider.radius as f32 / team.collider().radius as f32;
+
+    commands.entity(trigger.target()).with_child((
+        Transform::from_translation(to_vec2(sprite_translation).extend(0.))
+            .with_scale(Vec3::splat(scale)),
+        sprite,
+        AffineSprite::enabled(),
+        RepeatedSprite::default(),
+    ));
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
 #[require(Life, Transform, PhysicObject, Level)]
 pub enum Team {
     Player(PlayerBall),
@@ -125,6 +165,58 @@ impl Team {
     pub fn is_enemy(&self) -> bool {
         !self.is_friend()
     }
+
+    /// Multiply the damage of its hits on enemies, `None` for the enemies themselves. The
+    /// tree is a friend, enemies bumping into it get hurt too.
+    pub fn damage(&self) -> Option<Scalar> {
+        match self {
+            Team::Player(ball) => Some(ball.stats().damage),
+            Team::Enemy(EnemyBall::Tree) => Some(Scalar::new(1)),
+            Team::Enemy(_) => None,
+        }
+    }
+
+    pub fn collider(&self) -> CircleCollider {
+        match self {
+            Team::Player(ball) => ball.collider(),
+            Team::Enemy(ball) => ball.collider(),
+        }
+    }
+
+    /// Spawn a new ball of this team, already shot, centered on `center` and moving at
+    /// `velocity`.
+    pub fn spawn(&self, commands: &mut Commands, center: Vector, velocity: Vector) -> Entity {
+        let origin = self.collider().origin_for_center(center);
+        let transform = Transform::from_translation(to_vec2(origin).extend(0.));
+
+        match *self {
+            Team::Player(ball) => commands
+                .spawn((transform, ball.to_bundle(true)))
+                .insert(PhysicObject {
+                    velocity,
+                    ..ball.physic_object(true)
+                })
+                .id(),
+            Team::Enemy(ball) => commands
+                .spawn((transform, ball.to_bundle()))
+                .insert(PhysicObject {
+                    velocity,
+                    ..ball.physic_object()
+                })
+                .id(),
+        }
+    }
+}
+
+#[cfg(feature = "gba")]
+impl Team {
+    /// Width and height of the sprite, in pixels.
+    fn sprite_size(&self) -> u8 {
+        match self {
+            Team::Player(_) => PLAYER_SPRITE_SIZE,
+            Team::Enemy(ball) => ball.size() * 2,
+        }
+    }
 }
 
 #[derive(Component, Deref, DerefMut)]
@@ -136,13 +228,32 @@ impl Default for Life {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PlayerBall {
     Boy,
     Princess,
     Dog,
 }
 
+/// Sprites of the characters are 16x16, their collider can't be bigger.
+const PLAYER_SPRITE_SIZE: u8 = 16;
+
+/// What makes a character play differently from the others, see `PlayerBall::stats`.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerBallStats {
+    pub mass: Scalar,
+    /// Radius of the collider, at most 8 to fit in the sprite.
+    pub radius: u8,
+    pub drag: Scalar,
+    /// Multiply the impulse given by the launcher.
+    pub power: Scalar,
+    /// Multiply the damage of its hits, see `EnemyBall::damage`.
+    pub damage: Scalar,
+    /// Used by pressing A again while the ball moves.
+    pub ability: AbilityKind,
+}
+
+#[cfg(feature = "gba")]
 impl PlayerBall {
     fn sprite(&self, sprites: &Sprites) -> Sprite {
         match self {
@@ -154,23 +265,144 @@ impl PlayerBall {
 }
 
 impl PlayerBall {
-    pub fn to_bundle(&self, sprites: &Sprites, physic_enabled: bool) -> impl Bundle {
-        (
-            PhysicObject {
-                enable: physic_enabled,
-                ..Default::default()
+    /// Stats of every character, side by side to balance them.
+    pub fn stats(&self) -> PlayerBallStats {
+        match self {
+            // Does a bit of everything
+            Self::Boy => PlayerBallStats {
+                mass: Scalar::new(1),
+                radius: 8,
+                drag: num!(0.5),
+                power: Scalar::new(1),
+                damage: Scalar::new(1),
+                ability: AbilityKind::Split,
+            },
+            // Slow to launch but heavy, she pushes everything and hits hard
+            Self::Princess => PlayerBallStats {
+                mass: num!(1.5),
+                radius: 8,
+                drag: num!(0.4),
+                power: num!(0.85),
+                damage: num!(1.25),
+                ability: AbilityKind::PushWave,
             },
+            // Small and fast, slips through gaps but stops early
+            Self::Dog => PlayerBallStats {
+                mass: num!(0.6),
+                radius: 6,
+                drag: num!(0.75),
+                power: num!(1.25),
+                damage: Scalar::new(1),
+                ability: AbilityKind::Dash,
+            },
+        }
+    }
+
+    pub fn collider(&self) -> CircleCollider {
+        Self::centered_collider(self.stats().radius)
+    }
+
+    /// Collider of each of the two balls this one splits into.
+    pub fn half_collider(&self) -> CircleCollider {
+        Self::centered_collider(self.stats().radius * 5 / 8)
+    }
+
+    fn centered_collider(radius: u8) -> CircleCollider {
+        // Smaller colliders stay centered on the sprite
+        let offset = Scalar::new((PLAYER_SPRITE_SIZE / 2 - radius) as i32);
+
+        CircleCollider::new(radius).with_offset(Vector::new(offset, offset))
+    }
+
+    /// Impulse given to the ball by the launcher, from `launcher::impulse`.
+    pub fn launch_impulse(&self, impulse: Vec2) -> Vector {
+        from_vec2(impulse) * self.stats().power
+    }
+
+    pub fn collision_layers(&self) -> CollisionLayers {
+        // Player balls go through the ones already fired
+        CollisionLayers::new(PLAYER_LAYER, CollisionLayers::ALL & !PLAYER_LAYER)
+    }
+
+    pub fn physic_object(&self, physic_enabled: bool) -> PhysicObject {
+        let stats = self.stats();
+
+        PhysicObject {
+            enable: physic_enabled,
+            ccd: true,
+            mass: stats.mass,
+            drag: stats.drag,
+            ..Default::default()
+        }
+    }
+
+    /// The sprite is added by `BallPlugin` when the ball is spawned.
+    pub fn to_bundle(&self, physic_enabled: bool) -> impl Bundle {
+        (
+            self.physic_object(physic_enabled),
+            Team::Player(*self),
+            self.collider(),
+            self.collision_layers(),
+            Ability::new(self.stats().ability),
+        )
+    }
+
+    /// One of the two halves of a split ball, moving like `physic_object`. Their sprite is
+    /// shrunk to their collider and they can't use their ability.
+    pub fn half_bundle(&self, physic_object: PhysicObject) -> impl Bundle {
+        (
+            physic_object,
             Team::Player(*self),
-            CircleCollider::from(8),
-            children![(
-                self.sprite(sprites),
-                AffineSprite::enabled(),
-                RepeatedSprite::default(),
-            )],
+            self.half_collider(),
+            self.collision_layers(),
+            Ability::spent(self.stats().ability),
         )
     }
 }
 
+// Settings: Damage.
+/// Hits slower than this, in pixels per second, don't hurt.
+const MIN_IMPACT_SPEED: Scalar = Scalar::from_raw(30 << 8);
+/// Impact speed times `Hit::weight` dealing one damage.
+const MOMENTUM_PER_DAMAGE: Scalar = Scalar::from_raw(50 << 8);
+
+/// A friend ball, a player ball or the tree, hitting an enemy.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    /// Speed at which the balls were getting closer, see `Contact::impact_speed`.
+    pub impact_speed: Scalar,
+    /// Mass of the friend ball times its `Team::damage`.
+    pub weight: Scalar,
+    /// Direction from the center of the enemy toward the friend ball, as if the enemy
+    /// wasn't rotated.
+    pub direction: Vector,
+}
+
+/// Side of an enemy taking more damage, see `EnemyBall::weak_point`.
+#[derive(Clone, Copy, Debug)]
+pub struct WeakPoint {
+    /// Direction from the center of the enemy, as if it wasn't rotated.
+    pub direction: Vector,
+    /// Hits closer to `direction` than this cosine are on the weak point.
+    pub min_alignment: Scalar,
+    pub multiplier: Scalar,
+}
+
+// Settings: Death effects.
+/// Splits in two green blobs going apart.
+const RED_BLOB_ON_DEATH: &[DeathEffect] = &[DeathEffect::Spawn {
+    team: Team::Enemy(EnemyBall::GreenBlob),
+    count: 2,
+    // A quarter turn
+    spread: Scalar::from_raw(64),
+    distance: 13,
+}];
+const SNAKE_ON_DEATH: &[DeathEffect] = &[DeathEffect::Drop(Pickup::Recharge)];
+const GHOST_ON_DEATH: &[DeathEffect] = &[DeathEffect::Explosion {
+    radius: 48,
+    speed: Scalar::from_raw(160 << 8),
+}];
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum EnemyBall {
     GreenBlob,
@@ -180,6 +412,7 @@ pub enum EnemyBall {
     Tree,
 }
 
+#[cfg(feature = "gba")]
 impl EnemyBall {
     pub fn sprite(&self, sprites: &Sprites) -> Sprite {
         match self {
@@ -190,7 +423,9 @@ impl EnemyBall {
             Self::Tree => sprites.tree.clone(),
         }
     }
+}
 
+impl EnemyBall {
     pub fn size(&self) -> u8 {
         match self {
             Self::Tree => 16,
@@ -198,11 +433,17 @@ impl EnemyBall {
         }
     }
 
-    pub fn mass(&self) -> f32 {
+    pub fn kind(&self) -> RigidBodyKind {
         match self {
-            Self::Tree => 10000.,
-            Self::Snake => 0.6,
-            _ => 1.,
+            Self::Tree => RigidBodyKind::Static,
+            _ => RigidBodyKind::Dynamic,
+        }
+    }
+
+    pub fn mass(&self) -> Scalar {
+        match self {
+            Self::Snake => num!(0.6),
+            _ => Scalar::new(1),
         }
     }
 
@@ -215,24 +456,116 @@ impl EnemyBall {
         }
     }
 
+    /// Damage ignored from each hit.
+    pub fn armor(&self) -> u8 {
+        match self {
+            Self::Ghost => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn weak_point(&self) -> Option<WeakPoint> {
+        match self {
+            // The top of its sprite
+            Self::Snake => Some(WeakPoint {
+                direction: Vector::new(Scalar::new(0), Scalar::new(-1)),
+                min_alignment: num!(0.5),
+                multiplier: Scalar::new(2),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Life taken by `hit`. Any hit faster than `MIN_IMPACT_SPEED` deals at least one
+    /// damage before the armor.
+    pub fn damage(&self, hit: &Hit) -> u8 {
+        if hit.impact_speed < MIN_IMPACT_SPEED {
+            return 0;
+        }
+
+        let mut damage = hit.impact_speed * hit.weight / MOMENTUM_PER_DAMAGE;
+        if let Some(weak_point) = self.weak_point()
+            && hit.direction.dot(weak_point.direction) >= weak_point.min_alignment
+        {
+            damage *= weak_point.multiplier;
+        }
+
+        let damage = damage.floor().max(1) - self.armor() as i32;
+        damage.clamp(0, u8::MAX as i32) as u8
+    }
+
+    pub fn material(&self) -> PhysicMaterial {
+        match self {
+            Self::GreenBlob | Self::RedBlob => PhysicMaterial {
+                restitution: num!(0.6),
+                friction: num!(0.2),
+                ..default()
+            },
+            Self::Ghost => PhysicMaterial {
+                restitution_combine: CombineRule::Max,
+                ..default()
+            },
+            Self::Tree => PhysicMaterial {
+                restitution: Scalar::new(0),
+                friction: Scalar::new(1),
+                restitution_combine: CombineRule::Min,
+                friction_combine: CombineRule::Max,
+            },
+            Self::Snake => PhysicMaterial::default(),
+        }
+    }
+
+    pub fn collision_layers(&self) -> CollisionLayers {
+        match self {
+            // Ghosts float through the other enemies
+            Self::Ghost => CollisionLayers::new(ENEMY_LAYER, CollisionLayers::ALL & !ENEMY_LAYER),
+            Self::Tree => CollisionLayers::new(SCENERY_LAYER, CollisionLayers::ALL),
+            _ => CollisionLayers::new(ENEMY_LAYER, CollisionLayers::ALL),
+        }
+    }
+
     pub fn physic_object(&self) -> PhysicObject {
         PhysicObject {
+            kind: self.kind(),
             mass: self.mass(),
             ..default()
         }
     }
 
-    pub fn to_bundle(&self, sprites: &Sprites) -> impl Bundle {
+    pub fn collider(&self) -> CircleCollider {
+        CircleCollider::new(self.size())
+    }
+
+    /// What happens where the enemy dies, see `DeathEffect`.
+    pub fn on_death(&self) -> OnDeath {
+        match self {
+            Self::RedBlob => OnDeath(RED_BLOB_ON_DEATH),
+            Self::Snake => OnDeath(SNAKE_ON_DEATH),
+            Self::Ghost => OnDeath(GHOST_ON_DEATH),
+            Self::GreenBlob | Self::Tree => OnDeath::default(),
+        }
+    }
+
+    pub fn behavior(&self) -> EnemyBehavior {
+        match self {
+            Self::Snake => EnemyBehavior::slither(),
+            Self::Ghost => EnemyBehavior::drift(),
+            Self::GreenBlob => EnemyBehavior::hop(),
+            Self::RedBlob | Self::Tree => EnemyBehavior::Still,
+        }
+    }
+
+    /// The sprite is added by `BallPlugin` when the ball is spawned.
+    pub fn to_bundle(&self) -> impl Bundle {
         (
             Team::Enemy(*self),
             Life(self.life()),
-            CircleCollider::from(self.size()),
+            self.collider(),
             self.physic_object(),
-            children![(
-                self.sprite(sprites),
-                AffineSprite::enabled(),
-                RepeatedSprite::default(),
-            )],
+            self.material(),
+            self.collision_layers(),
+            self.behavior(),
+            self.on_death(),
         )
     }
 }
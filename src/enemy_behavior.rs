@@ -0,0 +1,252 @@
+use alloc::vec::Vec;
+use bevy::prelude::*;
+#[cfg(feature = "gba")]
+use bevy_mod_gba::Sprite;
+
+use crate::{
+    ball_type::{EnemyBall, PLAYER_LAYER, Team},
+    physic::{
+        CircleCollider, CollisionLayers, PhysicObject, PhysicSchedule, PhysicSet, PhysicStep,
+        Scalar, Vector, direction_and_length, run_physic_schedule,
+    },
+};
+
+// Settings: Enemy behaviors.
+/// Points a snake slithers through in a loop, from where it spawned, in pixels.
+const SNAKE_PATH: &[(i32, i32)] = &[
+    (-24, 0),
+    (-12, 6),
+    (0, 0),
+    (12, -6),
+    (24, 0),
+    (12, 6),
+    (0, 0),
+    (-12, -6),
+];
+/// Distance to the next point of the path for a snake to go toward the one after.
+const SNAKE_REACH: Scalar = Scalar::from_raw(4 << 8);
+const SNAKE_ACCELERATION: Scalar = Scalar::from_raw(40 << 8);
+const GHOST_ACCELERATION: Scalar = Scalar::from_raw(12 << 8);
+/// Seconds a ghost stays solid before turning intangible.
+const GHOST_SOLID_TIME: Scalar = Scalar::from_raw(2 << 8);
+/// Seconds a ghost stays intangible.
+const GHOST_INTANGIBLE_TIME: Scalar = Scalar::from_raw(1 << 8);
+/// Gap between a blob and a player ball for the blob to hop away, in pixels.
+const HOP_RANGE: Scalar = Scalar::from_raw(12 << 8);
+const HOP_SPEED: Scalar = Scalar::from_raw(60 << 8);
+/// Seconds between two hops.
+const HOP_COOLDOWN: Scalar = Scalar::from_raw(1 << 8);
+
+pub struct EnemyBehaviorPlugin;
+
+impl Plugin for EnemyBehaviorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, tick_enemy_timers.before(run_physic_schedule));
+        app.add_systems(
+            PhysicSchedule,
+            run_enemy_behaviors.before(PhysicSet::Integrate),
+        );
+
+        #[cfg(feature = "gba")]
+        app.add_systems(PostUpdate, blink_intangible_ghosts);
+    }
+}
+
+/// Center of the player controller of the current level.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LauncherCenter(pub Vector);
+
+/// What an enemy does on its own, see `EnemyBall::behavior`.
+///
+/// Enemies only move by giving themselves impulses, on each substep before the objects are
+/// moved, and only while a player ball is moving so the level still comes to rest. Their
+/// timers count down once per `FixedUpdate`, as substeps are too short for a `Scalar`.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub enum EnemyBehavior {
+    /// Sit still until hit.
+    #[default]
+    Still,
+    /// Slither along `SNAKE_PATH` around `home`, set to where the enemy is on the first
+    /// substep.
+    Slither { home: Option<Vector>, next: usize },
+    /// Drift toward the `LauncherCenter`, and let the player balls go through part of the
+    /// time by removing `PLAYER_LAYER` from its filters.
+    Drift { timer: Scalar, intangible: bool },
+    /// Hop away from the player balls coming near.
+    Hop { cooldown: Scalar },
+}
+
+impl EnemyBehavior {
+    pub fn slither() -> Self {
+        Self::Slither {
+            home: None,
+            next: 0,
+        }
+    }
+
+    pub fn drift() -> Self {
+        Self::Drift {
+            timer: GHOST_SOLID_TIME,
+            intangible: false,
+        }
+    }
+
+    pub fn hop() -> Self {
+        Self::Hop {
+            cooldown: Scalar::new(0),
+        }
+    }
+
+    pub fn is_intangible(&self) -> bool {
+        matches!(
+            self,
+            Self::Drift {
+                intangible: true,
+                ..
+            }
+        )
+    }
+}
+
+fn tick_enemy_timers(
+    time: Res<Time>,
+    mut enemies: Query<(&mut EnemyBehavior, &mut CollisionLayers, &PhysicObject)>,
+) {
+    let elapsed = Scalar::from_f32(time.delta_secs());
+
+    for (mut behavior, mut layers, object) in &mut enemies {
+        if !object.enable || !object.is_dynamic() {
+            continue;
+        }
+
+        match &mut *behavior {
+            EnemyBehavior::Still | EnemyBehavior::Slither { .. } => {}
+            EnemyBehavior::Drift { timer, intangible } => {
+                *timer -= elapsed;
+                if *timer > Scalar::new(0) {
+                    continue;
+                }
+
+                *intangible = !*intangible;
+                *timer = if *intangible {
+                    GHOST_INTANGIBLE_TIME
+                } else {
+                    GHOST_SOLID_TIME
+                };
+
+                let filters = EnemyBall::Ghost.collision_layers().filters;
+                layers.filters = if *intangible {
+                    filters & !PLAYER_LAYER
+                } else {
+                    filters
+                };
+            }
+            EnemyBehavior::Hop { cooldown } => {
+                *cooldown = (*cooldown - elapsed).max(Scalar::new(0));
+            }
+        }
+    }
+}
+
+fn run_enemy_behaviors(
+    step: Res<PhysicStep>,
+    launcher: Option<Res<LauncherCenter>>,
+    mut moving_balls: Local<Vec<(Vector, Scalar)>>,
+    balls: Query<(&PhysicObject, &CircleCollider, &Team), Without<EnemyBehavior>>,
+    mut enemies: Query<(&mut EnemyBehavior, &mut PhysicObject, &CircleCollider)>,
+) {
+    moving_balls.clear();
+    moving_balls.extend(
+        balls
+            .iter()
+            .filter(|(object, _, team)| matches!(team, Team::Player(_)) && object.is_awake())
+            .map(|(object, collider, _)| (collider.center(object), collider.radius())),
+    );
+    let in_play = !moving_balls.is_empty();
+
+    for (mut behavior, mut object, collider) in &mut enemies {
+        if !object.enable || !object.is_dynamic() {
+            continue;
+        }
+
+        let center = collider.center(&object);
+        let mass = object.mass;
+
+        match &mut *behavior {
+            EnemyBehavior::Still => {}
+            EnemyBehavior::Slither { home, next } => {
+                let home = *home.get_or_insert(center);
+                if !in_play {
+                    continue;
+                }
+
+                let (x, y) = SNAKE_PATH[*next];
+                let offset = home + Vector::new(Scalar::new(x), Scalar::new(y)) - center;
+                let distance = offset.magnitude();
+                if distance <= SNAKE_REACH {
+                    *next = (*next + 1) % SNAKE_PATH.len();
+                    continue;
+                }
+
+                object.impulse += step.scale(offset / distance * SNAKE_ACCELERATION) * mass;
+            }
+            EnemyBehavior::Drift { .. } => {
+                if !in_play {
+                    continue;
+                }
+                let Some(launcher) = &launcher else {
+                    continue;
+                };
+
+                let offset = launcher.0 - center;
+                let distance = offset.magnitude();
+                if distance > collider.radius() {
+                    object.impulse += step.scale(offset / distance * GHOST_ACCELERATION) * mass;
+                }
+            }
+            EnemyBehavior::Hop { cooldown } => {
+                if *cooldown > Scalar::new(0) {
+                    continue;
+                }
+
+                let near = moving_balls.iter().find(|(ball_center, ball_radius)| {
+                    (center - *ball_center).magnitude() - *ball_radius - collider.radius()
+                        < HOP_RANGE
+                });
+                let Some((ball_center, _)) = near else {
+                    continue;
+                };
+
+                if let Some((away, _)) = direction_and_length(center - *ball_center) {
+                    object.impulse += away * HOP_SPEED * mass;
+                    *cooldown = HOP_COOLDOWN;
+                }
+            }
+        }
+    }
+}
+
+/// Times per second an intangible ghost blinks.
+#[cfg(feature = "gba")]
+const GHOST_BLINK_RATE: f32 = 8.0;
+
+#[cfg(feature = "gba")]
+fn blink_intangible_ghosts(
+    time: Res<Time>,
+    ghosts: Query<(&EnemyBehavior, &Children)>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let blink = (time.elapsed_secs() * GHOST_BLINK_RATE * 2.0) as u32 % 2 == 0;
+
+    for (behavior, children) in &ghosts {
+        let EnemyBehavior::Drift { .. } = behavior else {
+            continue;
+        };
+
+        for child in children {
+            if let Ok(mut sprite) = sprites.get_mut(*child) {
+                sprite.visible = !behavior.is_intangible() || blink;
+            }
+        }
+    }
+}
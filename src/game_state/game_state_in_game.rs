@@ -1,14 +1,20 @@
 use core::usize;
 
+use agb::display::{object::PaletteVram, palette16::Palette16};
 use bevy::prelude::*;
 
+use crate::ability::{Ability, UseAbility};
 use crate::ball_type::Team;
 use crate::game_state::MyGameState;
 use crate::level::{LEVELS, LevelData, LevelSpawner, PlayerDirection};
-use crate::physic::PhysicObject;
+use crate::physic::{
+    CircleCollider, ContactCache, PhysicConfig, PhysicObject, PhysicStatus, PredictedPath, Scalar,
+    SensorCache, ShotPredictor, from_vec2, to_vec2,
+};
+use crate::render::{DOT_CENTER, Sprite, SpriteHandles, dot_sprite};
 use crate::sound_manager::SoundManager;
 use crate::text::{Size, Text, TextContent};
-use crate::{fade_transition, level::Level};
+use crate::{fade_transition, launcher, level::Level};
 
 #[derive(Resource, Default)]
 pub struct WantedLevel(pub Option<usize>, pub Option<usize>);
@@ -31,11 +37,24 @@ impl CurrentLevel {
     }
 }
 
+/// Dot showing the path the aimed ball will follow.
+#[derive(Component)]
+struct PredictionDot;
+
+// Settings: Shot prediction.
+const PREDICTION_DOTS: usize = 16;
+/// How far ahead the path is predicted, in seconds.
+const PREDICTION_TIME: Scalar = Scalar::from_raw(102);
+/// Physic steps between two dots.
+const PREDICTION_DOT_SPACING: usize = 3;
+
 #[derive(Resource, Default, Clone)]
 pub struct InGameData {
     time_started_pressing_to_rotate: f32,
     player_start_press_to_fire_time: Option<f32>,
     last_ball_fire_time: f64,
+    /// Its ability can be used while it moves.
+    last_ball_fired: Option<Entity>,
 
     next_player_ball_to_use: usize,
     nb_ball_fired: usize,
@@ -44,7 +63,6 @@ pub struct InGameData {
     is_success: bool,
 
     time_since_level_start: f64,
-    stabilized: bool,
 
     balls_text: Option<Entity>,
     levels_text: Option<Entity>,
@@ -59,9 +77,9 @@ pub fn init_state_in_game_system(app: &mut App) {
             Update,
             (
                 exec_load_level,
-                check_stabilized,
                 update_text,
                 player_control,
+                show_shot_prediction,
                 spawn_player_ball_update,
                 detect_finish_level,
             )
@@ -74,6 +92,7 @@ fn in_game_enter(
     mut commands: Commands,
     wanted_level: Option<Res<WantedLevel>>,
     mut sound_manager: SoundManager,
+    mut handles: NonSendMut<SpriteHandles>,
 ) {
     if wanted_level.is_none() {
         commands.insert_resource(WantedLevel(Some(0), Some(0)));
@@ -108,6 +127,16 @@ fn in_game_enter(
     //     .id()
     //     .into();
 
+    let mut palette = [0x0; 16];
+    palette[1] = 0x7FFF; // White
+    let palette = PaletteVram::new(&Palette16::new(palette)).unwrap();
+    let mut dot = dot_sprite(&mut handles, &palette, 1);
+    dot.visible = false;
+
+    for _ in 0..PREDICTION_DOTS {
+        commands.spawn((PredictionDot, Transform::IDENTITY, dot.clone()));
+    }
+
     commands.insert_resource(InGameData {
         time_started_pressing_to_rotate: 0.0,
         player_start_press_to_fire_time: None,
@@ -131,6 +160,9 @@ fn exec_load_level(
     in_game_data: ResMut<InGameData>,
     fade_external_data: ResMut<fade_transition::FadeExternalData>,
     time: Res<Time>,
+    mut physic_config: ResMut<PhysicConfig>,
+    mut contacts: ResMut<ContactCache>,
+    mut sensor_overlaps: ResMut<SensorCache>,
 ) {
     //info!("exec_load_level");
 
@@ -152,6 +184,10 @@ fn exec_load_level(
         level_spawner.commands.entity(entity).despawn();
     }
 
+    // The despawned objects must not carry their contacts over to the next level
+    contacts.clear();
+    sensor_overlaps.clear();
+
     level_spawner.current_level.player_ball_selected = None;
     level_spawner.current_level.player_entity = None;
 
@@ -175,6 +211,8 @@ fn exec_load_level(
 
     if let Some(level_to_load) = level_id.map(|id| LEVELS.get(id)).flatten() {
         // info!("Loading level {level_id}");
+        physic_config.boundary = level_to_load.arena.boundary();
+        commands.insert_resource(level_to_load.launcher_center());
         level_spawner.spawn_initial(level_to_load);
         level_spawner.current_level.level_index = load_level_request.0;
         load_level_request.1 = None;
@@ -281,12 +319,16 @@ fn update_text(
 }
 
 fn player_control(
+    mut commands: Commands,
     gamepad: Single<&Gamepad>,
     time: Res<Time>,
     mut runtime_data: ResMut<InGameData>,
     mut current_level: ResMut<CurrentLevel>,
     mut transforms: Query<&mut Transform>,
     mut physic_objects: Query<&mut PhysicObject>,
+    colliders: Query<&CircleCollider>,
+    teams: Query<&Team>,
+    abilities: Query<&Ability>,
 ) {
     if current_level.data().is_none() {
         return;
@@ -294,7 +336,12 @@ fn player_control(
 
     if let Some(_time_when_adding_finish_text) = runtime_data.text_success_fail_added_time {
         let impulse_force_ratio = get_impulse_ratio_based_on_input(&time, &runtime_data);
-        update_player_ball_selected_position(transforms, &current_level, impulse_force_ratio);
+        update_player_ball_selected_position(
+            transforms,
+            &colliders,
+            &current_level,
+            impulse_force_ratio,
+        );
         return;
     }
 
@@ -438,25 +485,40 @@ fn player_control(
     }
 
     if gamepad.just_pressed(GamepadButton::East) {
-        runtime_data.player_start_press_to_fire_time = Some(time.elapsed_secs());
-    } else if gamepad.just_released(GamepadButton::East) {
+        // Pressing A again while the last ball moves uses its ability instead of charging
+        if let Some(ball) = runtime_data.last_ball_fired
+            && let Ok(ability) = abilities.get(ball)
+            && let Ok(physic_object) = physic_objects.get(ball)
+            && ability.is_ready(physic_object)
+        {
+            commands.trigger_targets(UseAbility, ball);
+        } else {
+            runtime_data.player_start_press_to_fire_time = Some(time.elapsed_secs());
+        }
+    } else if gamepad.just_released(GamepadButton::East)
+        && runtime_data.player_start_press_to_fire_time.is_some()
+    {
         if let Some(player_id) = current_level.player_entity {
             let Ok(player_entity_transform) = transforms.get_mut(player_id) else {
                 return;
             };
 
             if let Some(player_ball_selected) = current_level.player_ball_selected {
-                let player_forward = get_player_controller_forward(player_entity_transform);
-                let impulse_force = get_impulse_force_based_on_input(&time, &runtime_data);
-                let impulse = player_forward * impulse_force;
+                let impulse_ratio = get_impulse_ratio_based_on_input(&time, &runtime_data);
+                let impulse = launcher::impulse(&player_entity_transform, impulse_ratio);
 
                 //info!("\n - Impulse Ratio: {} | Impuse Force: {}",get_impulse_ratio_based_on_input(&time, &runtime_data),impulse_force);
 
+                let Ok(&Team::Player(player_ball)) = teams.get(player_ball_selected) else {
+                    return;
+                };
+
                 let ball_physic_object_result = physic_objects.get_mut(player_ball_selected);
                 let mut ball_physic_object = ball_physic_object_result.unwrap();
-                ball_physic_object.impulse = Vec2::new(impulse.x, impulse.y);
+                ball_physic_object.impulse = player_ball.launch_impulse(impulse);
                 ball_physic_object.enable = true;
                 current_level.player_ball_selected = None;
+                runtime_data.last_ball_fired = Some(player_ball_selected);
                 runtime_data.player_start_press_to_fire_time = None;
                 runtime_data.last_ball_fire_time = time.elapsed_secs_f64();
 
@@ -466,7 +528,12 @@ fn player_control(
     }
 
     let impulse_force_ratio = get_impulse_ratio_based_on_input(&time, &runtime_data);
-    update_player_ball_selected_position(transforms, &current_level, impulse_force_ratio);
+    update_player_ball_selected_position(
+        transforms,
+        &colliders,
+        &current_level,
+        impulse_force_ratio,
+    );
 }
 
 fn spawn_player_ball_update(
@@ -495,36 +562,91 @@ fn spawn_player_ball_update(
         return;
     }
 
-    let target_position =
-        get_ball_position_on_player_controller(&mut transforms, current_level, 0.0);
+    let target_center = get_ball_center_on_player_controller(&mut transforms, current_level, 0.0);
 
     // info!("New Player Ball Spawned!");
     level_spawner.spawn_player_ball_at_index(
         level_data,
         runtime_data.next_player_ball_to_use,
-        target_position,
+        target_center,
     );
     runtime_data.next_player_ball_to_use += 1;
 }
 
+/// Show where the ball about to be shot will go with the current aim and charge.
+fn show_shot_prediction(
+    time: Res<Time>,
+    current_level: Res<CurrentLevel>,
+    runtime_data: Res<InGameData>,
+    controllers: Query<&Transform, Without<PredictionDot>>,
+    teams: Query<&Team>,
+    predictor: ShotPredictor,
+    mut path: Local<PredictedPath>,
+    mut dots: Query<(&mut Transform, &mut Sprite), With<PredictionDot>>,
+) {
+    path.points.clear();
+    path.bounce = None;
+
+    if let Some(ball) = current_level.player_ball_selected
+        && let Ok(&Team::Player(player_ball)) = teams.get(ball)
+        && let Some(controller) = current_level
+            .player_entity
+            .and_then(|player| controllers.get(player).ok())
+    {
+        let impulse_ratio = get_impulse_ratio_based_on_input(&time, &runtime_data);
+        let center = launcher::ball_center(controller, impulse_ratio);
+        let impulse = launcher::impulse(controller, impulse_ratio);
+
+        predictor.predict(
+            ball,
+            from_vec2(center.truncate()),
+            player_ball.launch_impulse(impulse),
+            PREDICTION_TIME,
+            &mut path,
+        );
+    }
+
+    // The bounce and the end of the path always get a dot
+    let spaced = path.points.iter().step_by(PREDICTION_DOT_SPACING);
+    let mut points = spaced
+        .chain(path.bounce.iter())
+        .chain(path.points.last())
+        .copied();
+
+    for (mut transform, mut sprite) in &mut dots {
+        let Some(point) = points.next() else {
+            sprite.visible = false;
+            continue;
+        };
+
+        sprite.visible = true;
+        transform.translation = (to_vec2(point) - DOT_CENTER).extend(0.);
+    }
+}
+
 fn update_player_ball_selected_position(
     mut transforms: Query<&mut Transform>,
+    colliders: &Query<&CircleCollider>,
     current_level: &CurrentLevel,
     impulse_ratio: f32,
 ) {
-    let target_position =
-        get_ball_position_on_player_controller(&mut transforms, current_level, impulse_ratio);
+    let target_center =
+        get_ball_center_on_player_controller(&mut transforms, current_level, impulse_ratio);
 
     if let Some(_player_ball_selected) = current_level.player_ball_selected {
+        let Ok(collider) = colliders.get(_player_ball_selected) else {
+            return;
+        };
         let Ok(mut player_ball_entity_transform) = transforms.get_mut(_player_ball_selected) else {
             return;
         };
 
-        player_ball_entity_transform.translation = target_position;
+        let target_position = collider.origin_for_center(from_vec2(target_center.truncate()));
+        player_ball_entity_transform.translation = to_vec2(target_position).extend(target_center.z);
     }
 }
 
-fn get_ball_position_on_player_controller(
+fn get_ball_center_on_player_controller(
     transforms: &mut Query<&mut Transform>,
     current_level: &CurrentLevel,
     impulse_ratio: f32,
@@ -534,26 +656,7 @@ fn get_ball_position_on_player_controller(
             return Vec3::ZERO;
         };
 
-        // Settings: Ball offset to throw.
-        let ball_front_player_offset_min_impulse = 3.0;
-        let ball_front_player_offset_max_impulse = 15.0;
-
-        let ratio_inverse = 1.0 - impulse_ratio;
-        let ball_front_player_offset = f32::lerp(
-            ball_front_player_offset_min_impulse,
-            ball_front_player_offset_max_impulse,
-            ratio_inverse,
-        );
-
-        let player_center_offset = Vec3::new(16.0, 16.0, 0.0);
-        let ball_center_offset = Vec3::new(-8.0, -8.0, 0.0);
-        let player_translation = player_entity_transform.translation;
-        let player_translation_center = player_translation + player_center_offset;
-        let player_forward = get_player_controller_forward(player_entity_transform);
-
-        return player_translation_center
-            + ball_center_offset
-            + player_forward * ball_front_player_offset;
+        return launcher::ball_center(&player_entity_transform, impulse_ratio);
     }
 
     return Vec3::ZERO;
@@ -566,23 +669,7 @@ fn remap(value: f32, min1: f32, max1: f32, min2: f32, max2: f32) -> f32 {
     return min2 + (max2 - min2) * valueRatioInRange;
 }
 
-fn remap_rotation(value: f32, min1: f32, max1: f32, min2: f32, max2: f32) -> f32 {
-    let minMaxRange1 = max1 - min1;
-    let valueRatioInRange = f32::clamp((value - min1) / minMaxRange1, 0.0, 1.0);
-
-    return (max2 - min2) * valueRatioInRange;
-}
-
-fn get_impulse_force_based_on_input(time: &Res<Time>, runtime_data: &ResMut<InGameData>) -> f32 {
-    // Settings: Throw Impulse.
-    let max_power = 190.0;
-    let min_power = 80.0;
-
-    let impulse_force_ratio = get_impulse_ratio_based_on_input(time, runtime_data);
-    return f32::lerp(min_power, max_power, impulse_force_ratio);
-}
-
-fn get_impulse_ratio_based_on_input(time: &Res<Time>, runtime_data: &ResMut<InGameData>) -> f32 {
+fn get_impulse_ratio_based_on_input(time: &Res<Time>, runtime_data: &InGameData) -> f32 {
     let mut impulse_force_ratio = 0.0;
 
     if let Some(time_since_press) = runtime_data.player_start_press_to_fire_time {
@@ -617,21 +704,13 @@ fn get_player_controller_translation(
     return Vec3::ZERO;
 }
 
-fn get_player_controller_forward(player_entity_transform: Mut<Transform>) -> Vec3 {
-    let player_rotation = player_entity_transform.rotation.to_euler(EulerRot::XYZ).2;
-    let player_rotation_converted = remap_rotation(player_rotation, -0.5, 0.5, 3.1416, -3.1416);
-
-    let direction_forward = Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, player_rotation_converted)
-        .mul_vec3(Vec3::new(0.0, 1.0, 0.0));
-    return direction_forward;
-}
-
 fn detect_finish_level(
     time: Res<Time>,
     enemies: Query<(Entity, &Team)>,
     current_level: Res<CurrentLevel>,
     mut wanted_level: ResMut<WantedLevel>,
     mut in_game_data: ResMut<InGameData>,
+    physic_status: Res<PhysicStatus>,
     fade_external_data: ResMut<fade_transition::FadeExternalData>,
 ) {
     if let Some(time_when_adding_finish_text) = in_game_data.text_success_fail_added_time {
@@ -674,7 +753,7 @@ fn detect_finish_level(
     if enemy_count == 0 {
         in_game_data.text_success_fail_added_time = Some(time.elapsed_secs_f64());
         in_game_data.is_success = true;
-    } else if in_game_data.stabilized
+    } else if physic_status.is_world_at_rest()
         && in_game_data.nb_ball_fired == current_level.data().unwrap().player_balls.len()
     {
         in_game_data.text_success_fail_added_time = Some(time.elapsed_secs_f64());
@@ -683,6 +762,7 @@ fn detect_finish_level(
 }
 
 fn in_game_exit(
+    prediction_dots: Query<Entity, With<PredictionDot>>,
     mut commands: Commands,
     level_entities: Query<Entity, With<Level>>,
     in_game_data: ResMut<InGameData>,
@@ -694,6 +774,10 @@ fn in_game_exit(
         commands.entity(entity).despawn();
     }
 
+    for entity in prediction_dots {
+        commands.entity(entity).despawn();
+    }
+
     if let Some(text_entity) = in_game_data.balls_text {
         commands.entity(text_entity).despawn();
     }
@@ -708,7 +792,3 @@ fn in_game_exit(
         ..Default::default()
     });
 }
-
-fn check_stabilized(mut in_game_data: ResMut<InGameData>, objects: Query<&PhysicObject>) {
-    in_game_data.stabilized = objects.iter().all(|po| po.velocity == Vec2::ZERO);
-}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::physic::{SPRITE_ANCHOR, from_vec2, to_vec2};
+
+// Settings: Throw Impulse.
+pub const MIN_POWER: f32 = 80.0;
+pub const MAX_POWER: f32 = 190.0;
+
+// Settings: Ball offset to throw.
+const BALL_OFFSET_MIN_IMPULSE: f32 = 3.0;
+const BALL_OFFSET_MAX_IMPULSE: f32 = 15.0;
+
+/// Size of the sprite of the player controller.
+const CONTROLLER_SIZE: f32 = 32.0;
+
+/// Direction a player controller with this `rotation` is shooting toward.
+///
+/// The rotation is in turns around Z, like the renderer reads it.
+pub fn forward(rotation: Quat) -> Vec3 {
+    let player_rotation = rotation.to_euler(EulerRot::XYZ).2;
+    let player_rotation_converted = remap_rotation(player_rotation, -0.5, 0.5, 3.1416, -3.1416);
+
+    Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, player_rotation_converted)
+        .mul_vec3(Vec3::new(0.0, 1.0, 0.0))
+}
+
+/// Center of the controller sprite.
+pub fn center(controller: &Transform) -> Vec3 {
+    let player_center_offset =
+        to_vec2(SPRITE_ANCHOR.center_offset(from_vec2(Vec2::splat(CONTROLLER_SIZE)))).extend(0.0);
+
+    controller.translation + player_center_offset
+}
+
+/// Where the center of the ball waiting to be shot is, in front of the controller. The ball
+/// is pulled back as `impulse_ratio` goes from 0 to 1.
+pub fn ball_center(controller: &Transform, impulse_ratio: f32) -> Vec3 {
+    let ratio_inverse = 1.0 - impulse_ratio;
+    let ball_front_player_offset = f32::lerp(
+        BALL_OFFSET_MIN_IMPULSE,
+        BALL_OFFSET_MAX_IMPULSE,
+        ratio_inverse,
+    );
+
+    center(controller) + forward(controller.rotation) * ball_front_player_offset
+}
+
+/// Impulse given to the ball when it is shot after charging for `impulse_ratio`, from 0 to 1.
+pub fn impulse(controller: &Transform, impulse_ratio: f32) -> Vec2 {
+    let impulse_force = f32::lerp(MIN_POWER, MAX_POWER, impulse_ratio);
+    (forward(controller.rotation) * impulse_force).truncate()
+}
+
+fn remap_rotation(value: f32, min1: f32, max1: f32, min2: f32, max2: f32) -> f32 {
+    let range = max1 - min1;
+    let ratio_in_range = f32::clamp((value - min1) / range, 0.0, 1.0);
+
+    (max2 - min2) * ratio_in_range
+}
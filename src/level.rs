@@ -1,24 +1,30 @@
+#[cfg(feature = "gba")]
+use bevy::ecs::{
+    children,
+    system::{ResMut, SystemParam},
+};
 use bevy::{
     app::Plugin,
-    ecs::{
-        children,
-        component::Component,
-        system::{Commands, ResMut, SystemParam},
-    },
+    ecs::{component::Component, system::Commands},
     math::{Quat, Vec2, Vec3},
     prelude::*,
     transform::components::Transform,
 };
 
-use crate::render::AffineSprite;
-
-use bevy::prelude::EulerRot;
-
-use crate::game_state::game_state_in_game::CurrentLevel;
-use crate::sprite_loader::Sprites;
 use crate::{
     ball_type::{EnemyBall, PlayerBall},
-    render::RepeatedSprite,
+    enemy_behavior::LauncherCenter,
+    launcher,
+    physic::{
+        Boundary, BoxCollider, PhysicZone, PhysicZoneEffect, PhysicZoneShape, PolygonCollider,
+        Scalar, SegmentCollider, from_vec2, to_vec2,
+    },
+};
+#[cfg(feature = "gba")]
+use crate::{
+    game_state::game_state_in_game::CurrentLevel,
+    render::{AffineSprite, RepeatedSprite},
+    sprite_loader::Sprites,
 };
 
 pub struct LevelPlugin;
@@ -86,6 +92,9 @@ pub const LEVELS: &[LevelData] = &[
         start_pos: Vec2::new(104., 24.),
         player_direction: PlayerDirection::Top,
         angle_width: 90.,
+        obstacles: &[],
+        zones: &[],
+        arena: DEFAULT_ARENA,
     },
     LevelData {
         title: "Hold the button! ",
@@ -95,6 +104,9 @@ pub const LEVELS: &[LevelData] = &[
         start_pos: Vec2::new(8., 70.),
         player_direction: PlayerDirection::Left,
         angle_width: 90.,
+        obstacles: &[],
+        zones: &[],
+        arena: DEFAULT_ARENA,
     },
     // LevelData {
     //     title: "They want revenge ",
@@ -140,6 +152,9 @@ pub const LEVELS: &[LevelData] = &[
         start_pos: Vec2::new(104., 24.),
         player_direction: PlayerDirection::Top,
         angle_width: 90.,
+        obstacles: &[],
+        zones: &[],
+        arena: DEFAULT_ARENA,
     },
     // LevelData {
     //     title: "They want revenge 2 ",
@@ -180,6 +195,9 @@ pub const LEVELS: &[LevelData] = &[
         start_pos: Vec2::new(104., 24.),
         player_direction: PlayerDirection::Top,
         angle_width: 90.,
+        obstacles: &[],
+        zones: &[],
+        arena: DEFAULT_ARENA,
     },
 ];
 
@@ -191,6 +209,18 @@ pub enum PlayerDirection {
     Right,
 }
 
+impl PlayerDirection {
+    /// Rotation of the player controller, in turns around Z.
+    pub fn rotation(self) -> Quat {
+        match self {
+            Self::Bottom => Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.0),
+            Self::Top => Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.5),
+            Self::Left => Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, -0.25),
+            Self::Right => Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.25),
+        }
+    }
+}
+
 pub struct LevelData {
     pub title: &'static str,
     pub player_balls: &'static [PlayerBall],
@@ -198,11 +228,193 @@ pub struct LevelData {
     pub start_pos: Vec2,
     pub angle_width: f32,
     pub player_direction: PlayerDirection,
+    pub obstacles: &'static [Obstacle],
+    pub zones: &'static [Zone],
+    pub arena: Arena,
+}
+
+/// Walls of the level. Balls going through one of the `gaps` are out.
+#[derive(Clone, Copy)]
+pub struct Arena {
+    pub shape: ArenaShape,
+    pub gaps: &'static [ArenaGap],
+}
+
+/// Inside of the walls drawn by `LevelSpawner::spawn_background`.
+pub const DEFAULT_ARENA: Arena = Arena {
+    shape: ArenaShape::Rect {
+        min: Vec2::new(16., 32.),
+        max: Vec2::new(224., 144.),
+    },
+    gaps: &[],
+};
+
+#[derive(Clone, Copy)]
+pub enum ArenaShape {
+    Rect { min: Vec2, max: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+    Polygon(&'static [Vec2]),
+}
+
+/// Opening in the wall around `center`.
+#[derive(Clone, Copy)]
+pub struct ArenaGap {
+    pub center: Vec2,
+    pub half_width: f32,
+}
+
+impl Arena {
+    pub fn boundary(&self) -> Boundary {
+        let boundary = match self.shape {
+            ArenaShape::Rect { min, max } => Boundary {
+                min: from_vec2(min),
+                max: from_vec2(max),
+                ..default()
+            },
+            ArenaShape::Circle { center, radius } => {
+                Boundary::circle(from_vec2(center), Scalar::from_f32(radius))
+            }
+            ArenaShape::Polygon(points) => {
+                Boundary::polygon(points.iter().map(|point| from_vec2(*point)))
+            }
+        };
+
+        self.gaps.iter().fold(boundary, |boundary, gap| {
+            boundary.with_gap(from_vec2(gap.center), Scalar::from_f32(gap.half_width))
+        })
+    }
+}
+
+/// Static wall placed inside a level.
+#[derive(Clone, Copy)]
+pub enum Obstacle {
+    Box { min: Vec2, max: Vec2 },
+    Segment { start: Vec2, end: Vec2 },
+    Polygon(&'static [Vec2]),
+}
+
+/// Area of a level changing how the balls move.
+#[derive(Clone, Copy)]
+pub struct Zone {
+    pub area: ZoneArea,
+    pub effect: ZoneEffect,
+}
+
+#[derive(Clone, Copy)]
+pub enum ZoneArea {
+    Rect { min: Vec2, max: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+}
+
+#[derive(Clone, Copy)]
+pub enum ZoneEffect {
+    /// Pull toward the center of the area, or push away when negative.
+    GravityWell(f32),
+    Wind(Vec2),
+    Conveyor(Vec2),
+    /// Drag multiplier, like `0.2` for ice or `4.` for mud.
+    Surface(f32),
 }
 
 #[derive(Component, Default)]
 pub struct Level;
 
+impl LevelData {
+    /// Transform of the player controller when the level starts.
+    pub fn controller_transform(&self) -> Transform {
+        let mut player_transform = Transform::IDENTITY;
+        player_transform.translation = Vec3::new(self.start_pos.x, self.start_pos.y, 0.0);
+        player_transform.rotation = self.player_direction.rotation();
+        player_transform
+    }
+
+    /// Center of the player controller, where ghosts drift toward.
+    pub fn launcher_center(&self) -> LauncherCenter {
+        let center = launcher::center(&self.controller_transform());
+        LauncherCenter(from_vec2(center.truncate()))
+    }
+
+    pub fn spawn_enemies_ball(&self, commands: &mut Commands) {
+        for (ball, pos) in self.enemy_balls {
+            commands.spawn((
+                Transform::from_translation(pos.extend(0.)),
+                ball.to_bundle(),
+            ));
+        }
+    }
+
+    pub fn spawn_obstacles(&self, commands: &mut Commands) {
+        for obstacle in self.obstacles {
+            let mut entity = commands.spawn((Level, Transform::IDENTITY));
+
+            match *obstacle {
+                Obstacle::Box { min, max } => entity.insert(BoxCollider {
+                    min: from_vec2(min),
+                    max: from_vec2(max),
+                }),
+                Obstacle::Segment { start, end } => entity.insert(SegmentCollider {
+                    start: from_vec2(start),
+                    end: from_vec2(end),
+                }),
+                Obstacle::Polygon(points) => entity.insert(PolygonCollider::new(
+                    points.iter().map(|point| from_vec2(*point)),
+                )),
+            };
+        }
+    }
+
+    pub fn spawn_zones(&self, commands: &mut Commands) {
+        for zone in self.zones {
+            let shape = match zone.area {
+                ZoneArea::Rect { min, max } => PhysicZoneShape::Rect {
+                    min: from_vec2(min),
+                    max: from_vec2(max),
+                },
+                ZoneArea::Circle { center, radius } => PhysicZoneShape::Circle {
+                    center: from_vec2(center),
+                    radius: Scalar::from_f32(radius),
+                },
+            };
+
+            let effect = match zone.effect {
+                ZoneEffect::GravityWell(strength) => {
+                    PhysicZoneEffect::GravityWell(Scalar::from_f32(strength))
+                }
+                ZoneEffect::Wind(force) => PhysicZoneEffect::Force(from_vec2(force)),
+                ZoneEffect::Conveyor(velocity) => PhysicZoneEffect::Conveyor(from_vec2(velocity)),
+                ZoneEffect::Surface(drag) => PhysicZoneEffect::Drag(Scalar::from_f32(drag)),
+            };
+
+            commands.spawn((Level, PhysicZone { shape, effect }));
+        }
+    }
+
+    /// Spawn a player ball ready to be shot, centered on `target_spawn_center`.
+    pub fn spawn_player_ball(
+        &self,
+        commands: &mut Commands,
+        player_ball_index: usize,
+        target_spawn_center: Vec3,
+    ) -> Option<Entity> {
+        let player_ball_info = self.player_balls.get(player_ball_index)?;
+        let target_spawn_position = player_ball_info
+            .collider()
+            .origin_for_center(from_vec2(target_spawn_center.truncate()));
+
+        Some(
+            commands
+                .spawn((
+                    Transform::from_translation(
+                        to_vec2(target_spawn_position).extend(target_spawn_center.z),
+                    ),
+                    player_ball_info.to_bundle(false),
+                ))
+                .id(),
+        )
+    }
+}
+
+#[cfg(feature = "gba")]
 #[derive(SystemParam)]
 pub struct LevelSpawner<'w, 's> {
     pub commands: Commands<'w, 's>,
@@ -210,30 +422,22 @@ pub struct LevelSpawner<'w, 's> {
     pub current_level: ResMut<'w, CurrentLevel>,
 }
 
+#[cfg(feature = "gba")]
 impl<'w, 's> LevelSpawner<'w, 's> {
     pub fn spawn_enemies_ball(&mut self, level_data: &LevelData) {
-        let LevelSpawner {
-            commands, sprites, ..
-        } = self;
-
-        for (ball, pos) in level_data.enemy_balls {
-            commands.spawn((
-                Transform::from_translation(pos.extend(0.)),
-                ball.to_bundle(&sprites),
-            ));
-        }
+        level_data.spawn_enemies_ball(&mut self.commands);
     }
 
     pub fn spawn_player_ball(
         &mut self,
         level_data: &LevelData,
         player_ball_index: usize,
-        target_spawn_position: Vec3,
+        target_spawn_center: Vec3,
     ) {
         let LevelSpawner {
             commands,
-            sprites,
             current_level,
+            ..
         } = self;
 
         if player_ball_index >= level_data.player_balls.len() {
@@ -245,15 +449,8 @@ impl<'w, 's> LevelSpawner<'w, 's> {
             return;
         }
 
-        let player_ball_info = level_data.player_balls[player_ball_index];
-        current_level.player_ball_selected = Some(
-            commands
-                .spawn((
-                    Transform::from_translation(target_spawn_position),
-                    player_ball_info.to_bundle(&sprites, false),
-                ))
-                .id(),
-        );
+        current_level.player_ball_selected =
+            level_data.spawn_player_ball(commands, player_ball_index, target_spawn_center);
     }
 
     fn spawn_player_controller(&mut self, level_data: &LevelData) {
@@ -263,31 +460,7 @@ impl<'w, 's> LevelSpawner<'w, 's> {
             current_level,
         } = self;
 
-
-        let player_rotation = match level_data.player_direction {
-            PlayerDirection::Bottom => {
-               Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.0)
-            }
-            PlayerDirection::Top => {
-                Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.5)
-            }
-            PlayerDirection::Left => {
-                 Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, -0.25)
-            }
-            PlayerDirection::Right => {
-                 Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.25)
-            }
-        };
-
-        // info!(
-        //     "Player Angle: {}",
-        //     player_rotation.to_euler(EulerRot::XYZ).2
-        // );
-
-        let mut player_transform = Transform::IDENTITY;
-        player_transform.translation =
-            Vec3::new(level_data.start_pos.x, level_data.start_pos.y, 0.0);
-        player_transform.rotation = player_rotation;
+        let player_transform = level_data.controller_transform();
 
         current_level.player_entity = Some(
             commands
@@ -305,6 +478,14 @@ impl<'w, 's> LevelSpawner<'w, 's> {
         );
     }
 
+    pub fn spawn_obstacles(&mut self, level_data: &LevelData) {
+        level_data.spawn_obstacles(&mut self.commands);
+    }
+
+    pub fn spawn_zones(&mut self, level_data: &LevelData) {
+        level_data.spawn_zones(&mut self.commands);
+    }
+
     fn spawn_background(&mut self) {
         let LevelSpawner {
             commands, sprites, ..
@@ -395,6 +576,8 @@ impl<'w, 's> LevelSpawner<'w, 's> {
     pub fn spawn_initial(&mut self, level_data: &LevelData) {
         self.spawn_player_controller(level_data);
         self.spawn_background();
+        self.spawn_obstacles(level_data);
+        self.spawn_zones(level_data);
         self.spawn_enemies_ball(level_data);
     }
 
@@ -402,8 +585,8 @@ impl<'w, 's> LevelSpawner<'w, 's> {
         &mut self,
         level_data: &LevelData,
         player_ball_index: usize,
-        target_spawn_position: Vec3,
+        target_spawn_center: Vec3,
     ) {
-        self.spawn_player_ball(level_data, player_ball_index, target_spawn_position);
+        self.spawn_player_ball(level_data, player_ball_index, target_spawn_center);
     }
 }
@@ -6,41 +6,75 @@
 #![no_std]
 
 //! [`agb`] provides a global allocator, allowing us to use items from the [`alloc`] crate.
+//! On the host, the `host` feature uses the one of `std` instead.
 extern crate alloc;
 
+#[cfg(feature = "gba")]
 use agb::display::{HEIGHT, WIDTH};
 use bevy::prelude::*;
-use bevy_mod_gba::Sprite;
+#[cfg(feature = "gba")]
 use level::LevelPlugin;
 use log::info;
 
+#[cfg(feature = "gba")]
 use crate::{
+    ability::AbilityPlugin,
     ball_type::BallPlugin,
     base::BasePlugin,
-    physic::{PhysicConfig, PhysicPlugin},
+    enemy_behavior::EnemyBehaviorPlugin,
+    on_death::OnDeathPlugin,
+    physic::{Boundary, PhysicConfig, PhysicPlugin, PhysicsDebugPlugin},
 };
 
+// The physic, the balls and the levels build without `agb`, everything else needs `gba`.
+pub mod ability;
 pub mod ball_type;
+#[cfg(feature = "gba")]
 pub mod base;
+pub mod enemy_behavior;
+#[cfg(feature = "gba")]
 pub mod fade_transition;
+#[cfg(feature = "gba")]
 pub mod game_state;
+pub mod launcher;
 pub mod level;
+pub mod on_death;
 pub mod physic;
+#[cfg(feature = "gba")]
 pub mod render;
+#[cfg(feature = "host")]
+pub mod simulation;
+#[cfg(feature = "gba")]
 pub mod sound_loader;
+#[cfg(feature = "gba")]
 pub mod sound_manager;
+#[cfg(feature = "gba")]
 pub mod sprite_loader;
+#[cfg(feature = "gba")]
 pub mod text;
+#[cfg(feature = "gba")]
 pub mod utils;
 
+#[cfg(feature = "gba")]
 pub struct GamePlugin;
 
+#[cfg(feature = "gba")]
 impl Plugin for GamePlugin {
     fn build(&self, mut app: &mut App) {
-        app.add_plugins((BasePlugin, LevelPlugin, PhysicPlugin, BallPlugin));
+        app.add_plugins((
+            BasePlugin,
+            LevelPlugin,
+            PhysicPlugin,
+            PhysicsDebugPlugin,
+            BallPlugin,
+            AbilityPlugin,
+            EnemyBehaviorPlugin,
+            OnDeathPlugin,
+        ));
 
         app.insert_resource(PhysicConfig {
-            boundary: Rect::new(16., 32., (WIDTH - 16) as f32, (HEIGHT - 16) as f32),
+            boundary: Boundary::new(16, 32, WIDTH - 16, HEIGHT - 16),
+            ..default()
         });
 
         game_state::init_game_state_system(&mut app);
@@ -0,0 +1,246 @@
+#[cfg(feature = "gba")]
+use agb::display::{
+    Priority,
+    object::{DynamicSprite, PaletteVram, Size},
+    palette16::Palette16,
+};
+use bevy::prelude::*;
+#[cfg(feature = "gba")]
+use bevy_mod_gba::{Sprite, SpriteHandles};
+
+use crate::{
+    ability::Ability,
+    ball_type::{PLAYER_LAYER, SCENERY_LAYER, Team},
+    level::Level,
+    physic::{
+        CircleCollider, CollisionLayers, PhysicObject, RigidBodyKind, Scalar, Sensor, SensorEnter,
+        Vector, direction_and_length, rotate, to_vec2,
+    },
+};
+
+/// Radius of the sensor of a pickup, in pixels.
+const PICKUP_RADIUS: u8 = 4;
+
+pub struct OnDeathPlugin;
+
+impl Plugin for OnDeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(explode);
+        app.add_observer(collect_pickup);
+
+        #[cfg(feature = "gba")]
+        app.add_observer(attach_pickup_sprite);
+    }
+}
+
+/// Effects applied where a ball dies, in order. Balls falling out of the arena don't
+/// apply them.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct OnDeath(pub &'static [DeathEffect]);
+
+#[derive(Clone, Copy, Debug)]
+pub enum DeathEffect {
+    /// Spawn `count` balls of `team` `distance` pixels away, spread over `spread` turns
+    /// around the direction the dying ball was going. They keep its speed.
+    Spawn {
+        team: Team,
+        count: u8,
+        spread: Scalar,
+        distance: u8,
+    },
+    /// Push the objects within `radius` pixels away, up to `speed` for the closest ones.
+    Explosion { radius: u8, speed: Scalar },
+    /// Leave a pickup behind.
+    Drop(Pickup),
+}
+
+impl OnDeath {
+    /// Apply the effects of a ball dying at `center` while moving at `velocity`.
+    pub fn apply(&self, commands: &mut Commands, center: Vector, velocity: Vector) {
+        for effect in self.0 {
+            effect.apply(commands, center, velocity);
+        }
+    }
+}
+
+impl DeathEffect {
+    fn apply(&self, commands: &mut Commands, center: Vector, velocity: Vector) {
+        match *self {
+            DeathEffect::Spawn {
+                team,
+                count,
+                spread,
+                distance,
+            } => {
+                let (direction, speed) = direction_and_length(velocity)
+                    .unwrap_or((Vector::new(Scalar::new(0), Scalar::new(1)), Scalar::new(0)));
+
+                for index in 0..count {
+                    let turns = if count > 1 {
+                        spread * index as i32 / (count as i32 - 1) - spread / 2
+                    } else {
+                        Scalar::new(0)
+                    };
+                    let direction = rotate(direction, turns);
+
+                    team.spawn(
+                        commands,
+                        center + direction * distance as i32,
+                        direction * speed,
+                    );
+                }
+            }
+            DeathEffect::Explosion { radius, speed } => {
+                commands.trigger(Explosion {
+                    center,
+                    radius: Scalar::new(radius as i32),
+                    speed,
+                });
+            }
+            DeathEffect::Drop(pickup) => {
+                let collider = pickup.collider();
+                let origin = collider.origin_for_center(center);
+
+                commands.spawn((
+                    Transform::from_translation(to_vec2(origin).extend(0.)),
+                    pickup.to_bundle(),
+                ));
+            }
+        }
+    }
+}
+
+/// Push every dynamic object within `radius` of `center` away, faster the closer it is.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Explosion {
+    pub center: Vector,
+    pub radius: Scalar,
+    pub speed: Scalar,
+}
+
+fn explode(
+    explosion: Trigger<Explosion>,
+    mut objects: Query<(&mut PhysicObject, &CircleCollider)>,
+) {
+    for (mut object, collider) in &mut objects {
+        if !object.enable || !object.is_dynamic() {
+            continue;
+        }
+
+        let offset = collider.center(&object) - explosion.center;
+        let Some((away, distance)) = direction_and_length(offset) else {
+            continue;
+        };
+        let reach = explosion.radius + collider.radius();
+        if distance >= reach {
+            continue;
+        }
+
+        let speed = explosion.speed * (reach - distance) / reach;
+        let mass = object.mass;
+        object.impulse += away * speed * mass;
+    }
+}
+
+/// Sensor collected by the first player ball going through it.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pickup {
+    /// The ability of the ball collecting it can be used again.
+    Recharge,
+}
+
+impl Pickup {
+    pub fn collider(&self) -> CircleCollider {
+        CircleCollider::new(PICKUP_RADIUS)
+    }
+
+    pub fn to_bundle(&self) -> impl Bundle {
+        (
+            *self,
+            Level,
+            Sensor,
+            self.collider(),
+            PhysicObject {
+                kind: RigidBodyKind::Static,
+                ..default()
+            },
+            // Only player balls collect them
+            CollisionLayers::new(SCENERY_LAYER, PLAYER_LAYER),
+        )
+    }
+}
+
+fn collect_pickup(
+    trigger: Trigger<SensorEnter>,
+    mut commands: Commands,
+    pickups: Query<&Pickup>,
+    mut balls: Query<(&Team, Option<&mut Ability>)>,
+) {
+    if trigger.target() != trigger.sensor {
+        return;
+    }
+
+    let Ok(pickup) = pickups.get(trigger.sensor) else {
+        return;
+    };
+    let Ok((Team::Player(_), ability)) = balls.get_mut(trigger.entity) else {
+        return;
+    };
+
+    match pickup {
+        Pickup::Recharge => match ability {
+            // Inserted again so it gets its cue back
+            Some(ability) if ability.used => {
+                let kind = ability.kind;
+                commands.entity(trigger.entity).insert(Ability {
+                    cooldown: 0,
+                    ..Ability::new(kind)
+                });
+            }
+            Some(mut ability) => ability.cooldown = 0,
+            None => {}
+        },
+    }
+
+    commands.entity(trigger.sensor).despawn();
+}
+
+/// Give a new pickup a blue diamond sprite, centered on its sensor.
+#[cfg(feature = "gba")]
+fn attach_pickup_sprite(
+    trigger: Trigger<OnAdd, Pickup>,
+    mut commands: Commands,
+    colliders: Query<&CircleCollider>,
+    mut handles: NonSendMut<SpriteHandles>,
+    mut pickup_sprite: Local<Option<Sprite>>,
+) {
+    let sprite = pickup_sprite.get_or_insert_with(|| {
+        let mut palette = [0x0; 16];
+        palette[1] = 0x7E00; // Blue
+        let palette = PaletteVram::new(&Palette16::new(palette)).unwrap();
+
+        // Diamond centered on the sprite
+        let mut sprite = DynamicSprite::new(Size::S8x8);
+        for y in 0..8 {
+            for x in 0..8 {
+                if (2 * x - 7i32).abs() + (2 * y - 7i32).abs() <= 6 {
+                    sprite.set_pixel(x as usize, y as usize, 1);
+                }
+            }
+        }
+
+        let mut sprite = Sprite::new(handles.add(sprite.to_vram(palette)));
+        sprite.priority = Priority::P1;
+        sprite
+    });
+
+    let Ok(collider) = colliders.get(trigger.target()) else {
+        return;
+    };
+    let sprite_size = Vector::new(Scalar::new(8), Scalar::new(8));
+    let translation = to_vec2(collider.sprite_translation(sprite_size)).extend(0.);
+
+    commands
+        .entity(trigger.target())
+        .with_child((Transform::from_translation(translation), sprite.clone()));
+}
@@ -1,26 +1,248 @@
+#[cfg(feature = "gba")]
 use agb::display::{HEIGHT, WIDTH};
-use bevy::prelude::*;
+use agb_fixnum::{Num, Vector2D, num};
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use core::ops::{Div, Mul};
+
+mod boundary;
+mod broad_phase;
+mod ccd;
+mod contact;
+#[cfg(feature = "gba")]
+mod debug;
+mod layers;
+mod material;
+mod obstacle;
+mod prediction;
+mod query;
+mod sensor;
+mod zone;
+
+pub use boundary::{Boundary, BoundaryGap, BoundaryShape};
+pub use broad_phase::BroadPhaseGrid;
+use ccd::SweepTarget;
+pub use contact::{
+    CollisionEnded, CollisionStarted, CollisionStay, Contact, ContactCache, update_contacts,
+};
+#[cfg(feature = "gba")]
+pub use debug::{PhysicsDebug, PhysicsDebugPlugin};
+pub use layers::CollisionLayers;
+pub use material::{CombineRule, PhysicMaterial};
+use obstacle::Obstacles;
+pub use obstacle::{BoxCollider, ObstacleShape, PolygonCollider, SegmentCollider};
+pub use prediction::{PredictedPath, ShotPredictor};
+pub use query::{ShapeHit, SpatialQuery, SpatialQueryFilter};
+pub use sensor::{Sensor, SensorCache, SensorEnter, SensorExit, SensorOverlap, update_sensors};
+use zone::ZoneInfluence;
+pub use zone::{PhysicZone, PhysicZoneEffect, PhysicZoneShape};
+
 pub struct PhysicPlugin;
 
 impl Plugin for PhysicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, move_physic_objects);
         app.init_resource::<PhysicConfig>();
+        app.init_resource::<PhysicStep>();
+        app.init_resource::<BroadPhaseGrid>();
+        app.init_resource::<ContactCache>();
+        app.init_resource::<SensorCache>();
+        app.init_resource::<PhysicStatus>();
+
+        app.init_schedule(PhysicSchedule);
+        app.configure_sets(
+            PhysicSchedule,
+            (
+                PhysicSet::Integrate,
+                PhysicSet::BroadPhase,
+                PhysicSet::NarrowPhase,
+                PhysicSet::Resolve,
+                PhysicSet::Boundary,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            PhysicSchedule,
+            (
+                move_physic_objects.in_set(PhysicSet::Integrate),
+                update_broad_phase.in_set(PhysicSet::BroadPhase),
+                detect_collision.in_set(PhysicSet::NarrowPhase),
+                (collide_with_obstacles, update_contacts, update_sensors)
+                    .chain()
+                    .in_set(PhysicSet::Resolve),
+                keep_object_in_boundary.in_set(PhysicSet::Boundary),
+            ),
+        );
+
         app.add_systems(
-            PostUpdate,
-            (detect_collision, keep_object_in_boundary)
-                .chain()
-                .after(TransformSystem::TransformPropagate),
+            FixedUpdate,
+            (run_physic_schedule, update_physic_status, sync_transforms).chain(),
         );
+
+        app.add_observer(init_physic_object);
         app.add_observer(handle_collision);
     }
 }
 
+/// Schedule running one substep of the simulation. It is run `PhysicConfig::substeps`
+/// times on each `FixedUpdate`.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PhysicSchedule;
+
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PhysicSet {
+    /// Apply impulses and move the objects.
+    Integrate,
+    /// Find the pairs of objects that could collide.
+    BroadPhase,
+    /// Find the pairs of objects that do collide and trigger `Collision` on them.
+    NarrowPhase,
+    /// `Collision` between objects are resolved by observers before this set runs.
+    /// Contacts with obstacles are resolved in it.
+    Resolve,
+    /// Keep objects inside `PhysicConfig::boundary`.
+    Boundary,
+}
+
+/// Duration of the current substep, `fixed_delta / substeps` seconds.
+///
+/// That duration isn't kept as a `Scalar`: 1/64 second is only 4 raw, so most divisions
+/// of it would be rounded down, or even to zero. Use `scale` instead, which divides after
+/// multiplying by the fixed delta.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PhysicStep {
+    /// Duration of a `FixedUpdate`, in seconds.
+    pub fixed_delta: Scalar,
+    pub substeps: u8,
+}
+
+impl Default for PhysicStep {
+    fn default() -> Self {
+        Self {
+            fixed_delta: Scalar::new(0),
+            substeps: 1,
+        }
+    }
+}
+
+impl PhysicStep {
+    /// Substeps of a `FixedUpdate` lasting `fixed_delta` seconds.
+    pub fn new(fixed_delta: f32, config: &PhysicConfig) -> Self {
+        Self {
+            fixed_delta: Scalar::from_f32(fixed_delta),
+            substeps: config.substeps.max(1),
+        }
+    }
+
+    /// How much `rate`, an amount per second, adds up to during the substep.
+    pub fn scale<T>(&self, rate: T) -> T
+    where
+        T: Mul<Scalar, Output = T> + Div<i32, Output = T>,
+    {
+        rate * self.fixed_delta / self.substeps as i32
+    }
+
+    /// Number of substeps lasting `duration` seconds.
+    pub fn count(&self, duration: Scalar) -> i32 {
+        (duration * self.substeps as i32 / self.fixed_delta).floor()
+    }
+}
+
+/// Run the `PhysicSchedule` once per substep, in `FixedUpdate`.
+pub fn run_physic_schedule(world: &mut World) {
+    let fixed_delta = world.resource::<Time<Fixed>>().delta_secs();
+    let step = PhysicStep::new(fixed_delta, world.resource::<PhysicConfig>());
+
+    world.insert_resource(step);
+
+    for _ in 0..step.substeps {
+        world.run_schedule(PhysicSchedule);
+    }
+}
+
+/// State of the whole simulation, updated after each `FixedUpdate`.
 #[derive(Resource, Default)]
+pub struct PhysicStatus {
+    at_rest: bool,
+}
+
+impl PhysicStatus {
+    /// Nothing moves anymore: every dynamic object is sleeping and every kinematic one is
+    /// stopped. Never true when sleeping is disabled.
+    pub fn is_world_at_rest(&self) -> bool {
+        self.at_rest
+    }
+}
+
+fn update_physic_status(mut status: ResMut<PhysicStatus>, physic_objects: Query<&PhysicObject>) {
+    status.at_rest = physic_objects.iter().all(PhysicObject::is_at_rest);
+}
+
+/// Fixed point number used by the whole physic simulation.
+///
+/// The GBA has no FPU, so everything in here avoid `f32` to stay fast and deterministic.
+pub type Scalar = Num<i32, 8>;
+pub type Vector = Vector2D<Scalar>;
+
+pub fn scalar_to_f32(value: Scalar) -> f32 {
+    value.to_raw() as f32 / (1 << 8) as f32
+}
+
+pub fn to_vec2(value: Vector) -> Vec2 {
+    Vec2::new(scalar_to_f32(value.x), scalar_to_f32(value.y))
+}
+
+pub fn from_vec2(value: Vec2) -> Vector {
+    Vector::new(Scalar::from_f32(value.x), Scalar::from_f32(value.y))
+}
+
+/// `value` rotated by `turns`, in the direction objects with a positive angular velocity
+/// turn.
+pub fn rotate(value: Vector, turns: Scalar) -> Vector {
+    let (cos, sin) = (turns.cos(), turns.sin());
+    Vector::new(value.x * cos - value.y * sin, value.x * sin + value.y * cos)
+}
+
+/// Unit vector along `value` and its length, or `None` if `value` is too short for its
+/// length to be anything but zero in fixed point, under 1/16 pixel.
+pub fn direction_and_length(value: Vector) -> Option<(Vector, Scalar)> {
+    let length = value.magnitude();
+    (length != Scalar::new(0)).then(|| (value / length, length))
+}
+
+/// `value` rotated by a quarter turn.
+pub fn perpendicular(value: Vector) -> Vector {
+    Vector::new(-value.y, value.x)
+}
+
+pub fn cross(a: Vector, b: Vector) -> Scalar {
+    a.x * b.y - a.y * b.x
+}
+
+#[derive(Resource)]
 pub struct PhysicConfig {
-    pub boundary: Rect,
+    pub boundary: Boundary,
+    pub wall_material: PhysicMaterial,
+    /// Number of times the simulation is stepped on each `FixedUpdate`.
+    pub substeps: u8,
+    /// How much a spinning object curve its path.
+    pub spin_curve: Scalar,
+    /// Number of steps an object must stay almost still before falling asleep.
+    /// Zero disables sleeping.
+    pub sleep_steps: u8,
 }
 
+impl Default for PhysicConfig {
+    fn default() -> Self {
+        Self {
+            boundary: Boundary::default(),
+            wall_material: PhysicMaterial::default(),
+            substeps: 1,
+            spin_curve: num!(0.05),
+            sleep_steps: 30,
+        }
+    }
+}
+
+#[cfg(feature = "gba")]
 impl PhysicConfig {
     pub fn screen_boundary() -> Self {
         let mut new_val = Self::default();
@@ -29,8 +251,7 @@ impl PhysicConfig {
     }
 
     pub fn set_screen_boundary(&mut self) {
-        self.boundary.min = Vec2::ZERO;
-        self.boundary.max = Vec2::new(WIDTH as f32, HEIGHT as f32);
+        self.boundary = Boundary::new(0, 0, WIDTH, HEIGHT);
     }
 
     pub fn with_screen_boundary(mut self) -> Self {
@@ -39,73 +260,420 @@ impl PhysicConfig {
     }
 }
 
-#[derive(Component)]
-#[require(PhysicObject, Transform)]
+/// Where the origin of an entity, its `Transform` translation, is on its shape.
+///
+/// Sprites are drawn from their top-left corner, see `SPRITE_ANCHOR`, so with `TopLeft` an
+/// entity and its sprite share the same origin.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    /// The sprite is then a child moved by half its size, so it is also moved around
+    /// when the entity rotates. Rotating entities should keep `TopLeft`.
+    Center,
+}
+
+/// Sprites are drawn with their top-left corner on their translation, like the hardware does.
+pub const SPRITE_ANCHOR: Anchor = Anchor::TopLeft;
+
+impl Anchor {
+    /// Offset from the origin to the center of a shape of size `size`.
+    pub fn center_offset(self, size: Vector) -> Vector {
+        match self {
+            Self::TopLeft => size / 2,
+            Self::Center => Vector::default(),
+        }
+    }
+}
+
+/// Circle used for collisions. Its center is found from the origin of the entity with
+/// the `anchor`, then moved by `offset`.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(PhysicObject, PhysicMaterial, CollisionLayers, Transform)]
 pub struct CircleCollider {
     pub radius: u8,
+    pub anchor: Anchor,
+    pub offset: Vector,
 }
 
 impl CircleCollider {
-    pub fn center(&self, global_transform: &GlobalTransform) -> Vec3 {
-        global_transform.translation() + Vec3::new(self.radius as f32, self.radius as f32, 0.)
+    pub fn new(radius: u8) -> Self {
+        Self {
+            radius,
+            anchor: Anchor::default(),
+            offset: Vector::default(),
+        }
+    }
+
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vector) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn radius(&self) -> Scalar {
+        Scalar::new(self.radius as i32)
+    }
+
+    fn size(&self) -> Vector {
+        let diameter = self.radius() * 2;
+        Vector::new(diameter, diameter)
+    }
+
+    /// Offset from the origin of the entity to the center of the circle.
+    pub fn center_offset(&self) -> Vector {
+        self.anchor.center_offset(self.size()) + self.offset
+    }
+
+    pub fn center(&self, physic_object: &PhysicObject) -> Vector {
+        physic_object.position + self.center_offset()
+    }
+
+    /// Origin to give an entity so its circle is centered on `center`.
+    pub fn origin_for_center(&self, center: Vector) -> Vector {
+        center - self.center_offset()
+    }
+
+    /// Translation, relative to the entity, of a sprite of `sprite_size` so it is drawn
+    /// centered on the circle.
+    pub fn sprite_translation(&self, sprite_size: Vector) -> Vector {
+        self.center_offset() - SPRITE_ANCHOR.center_offset(sprite_size)
     }
 }
 
 impl From<u8> for CircleCollider {
     fn from(value: u8) -> Self {
-        Self { radius: value }
+        Self::new(value)
     }
 }
 
-#[derive(Component, Debug)]
+/// State of a body in the physic simulation.
+///
+/// `position` is the source of truth while the object is enabled and is copied to the
+/// `Transform` for rendering. While disabled, the object follow its `Transform` instead.
+///
+/// With `ccd`, the movement of the object is always swept against the other colliders
+/// so it can't go through them. Objects moving more than half their radius in one step
+/// are swept even without it.
+///
+/// `rotation` is in turns and `angular_velocity` in radians per second. An `inertia` of
+/// zero is replaced by the one of a disk the size of the `CircleCollider`.
+///
+/// A dynamic object staying almost still for `PhysicConfig::sleep_steps` falls asleep: it
+/// stops being simulated until an impulse or a moving object wakes it up.
+#[derive(Component, Clone, Debug)]
 pub struct PhysicObject {
     pub enable: bool,
-    pub mass: f32,
-    pub impulse: Vec2,
-    pub velocity: Vec2,
-    pub drag: f32,
+    pub kind: RigidBodyKind,
+    pub ccd: bool,
+    pub mass: Scalar,
+    pub inertia: Scalar,
+    pub position: Vector,
+    pub rotation: Scalar,
+    pub impulse: Vector,
+    pub angular_impulse: Scalar,
+    pub velocity: Vector,
+    pub angular_velocity: Scalar,
+    pub drag: Scalar,
+    pub sleeping: bool,
+    /// Number of steps the object has been almost still.
+    pub sleep_timer: u8,
 }
 
 impl Default for PhysicObject {
     fn default() -> Self {
         Self {
             enable: true,
-            mass: 1.,
-            impulse: Vec2::ZERO,
-            velocity: Vec2::ZERO,
-            drag: 0.5,
+            kind: RigidBodyKind::Dynamic,
+            ccd: false,
+            mass: Scalar::new(1),
+            inertia: Scalar::new(0),
+            position: Vector::default(),
+            rotation: Scalar::new(0),
+            impulse: Vector::default(),
+            angular_impulse: Scalar::new(0),
+            velocity: Vector::default(),
+            angular_velocity: Scalar::new(0),
+            drag: num!(0.5),
+            sleeping: false,
+            sleep_timer: 0,
         }
     }
 }
 
-const SPEED_SQUARED_TO_ZERO: f32 = 1.;
+impl PhysicObject {
+    /// Inverse of the mass, zero for objects that can't be pushed.
+    pub fn inverse_mass(&self) -> Scalar {
+        match self.kind {
+            RigidBodyKind::Dynamic if !self.sleeping => Scalar::new(1) / self.mass,
+            _ => Scalar::new(0),
+        }
+    }
+
+    /// Inverse of the moment of inertia, zero for objects that can't be spun.
+    pub fn inverse_inertia(&self) -> Scalar {
+        if self.is_dynamic() && !self.sleeping && self.inertia > Scalar::new(0) {
+            Scalar::new(1) / self.inertia
+        } else {
+            Scalar::new(0)
+        }
+    }
+
+    /// Apply the impulses, the drag, the zone the object is in and its spin to the
+    /// velocities. Returns how far the object wants to move this step.
+    fn integrate_velocity(
+        &mut self,
+        step: &PhysicStep,
+        zone: &ZoneInfluence,
+        spin_curve: Scalar,
+    ) -> Vector {
+        let relative_velocity = self.velocity - zone.surface_velocity;
+        let drag = self.drag * zone.drag_factor;
+        self.velocity +=
+            self.impulse / self.mass + step.scale(zone.acceleration - relative_velocity * drag);
+
+        let angular_impulse = self.angular_impulse * self.inverse_inertia();
+        let angular_velocity = self.angular_velocity;
+        self.angular_velocity += angular_impulse - step.scale(angular_velocity * drag);
+
+        // The spin curve the path of the object
+        let velocity = self.velocity;
+        self.velocity += perpendicular(velocity) * step.scale(self.angular_velocity * spin_curve);
+
+        step.scale(self.velocity)
+    }
+
+    /// Move the object by `displacement`, which can be shorter than the one returned by
+    /// `integrate_velocity` when it is stopped by something, and end the step.
+    fn advance(&mut self, displacement: Vector, step: &PhysicStep, sleep_steps: u8) {
+        self.position += displacement;
+        self.integrate_rotation(step);
+
+        self.update_sleep(sleep_steps);
+
+        self.impulse = Vector::default();
+        self.angular_impulse = Scalar::new(0);
+    }
+
+    fn integrate_rotation(&mut self, step: &PhysicStep) {
+        let rotation = self.rotation + step.scale(self.angular_velocity) / TAU;
+        self.rotation = rotation - Scalar::new(rotation.floor());
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.kind == RigidBodyKind::Dynamic
+    }
+
+    /// Can the object move on its own this step.
+    pub fn is_awake(&self) -> bool {
+        self.enable && self.kind != RigidBodyKind::Static && !self.sleeping
+    }
+
+    /// Is the object not moving and won't move until something hit it.
+    pub fn is_at_rest(&self) -> bool {
+        match self.kind {
+            _ if !self.enable => true,
+            RigidBodyKind::Static => true,
+            RigidBodyKind::Kinematic => {
+                self.velocity == Vector::default() && self.angular_velocity == Scalar::new(0)
+            }
+            RigidBodyKind::Dynamic => self.sleeping,
+        }
+    }
+
+    pub fn sleep(&mut self) {
+        self.sleeping = true;
+        self.velocity = Vector::default();
+        self.angular_velocity = Scalar::new(0);
+    }
+
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0;
+    }
+
+    fn update_sleep(&mut self, sleep_steps: u8) {
+        if sleep_steps == 0
+            || self.velocity.magnitude_squared() >= SLEEP_SPEED_SQUARED
+            || self.angular_velocity.abs() >= SLEEP_ANGULAR_SPEED
+        {
+            self.sleep_timer = 0;
+            return;
+        }
+
+        self.sleep_timer = self.sleep_timer.saturating_add(1);
+        if self.sleep_timer >= sleep_steps {
+            self.sleep();
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RigidBodyKind {
+    /// Never move and can't be pushed, like it had an infinite mass.
+    Static,
+    /// Moved only by its `velocity`, set by gameplay code. It push dynamic objects but
+    /// ignore impulses, drag and walls.
+    Kinematic,
+    /// Moved by the simulation.
+    #[default]
+    Dynamic,
+}
+
+/// Speeds under which an object is considered still and can fall asleep.
+const SLEEP_SPEED_SQUARED: Scalar = Scalar::from_raw(1 << 8);
+const SLEEP_ANGULAR_SPEED: Scalar = Scalar::from_raw(1 << 7);
+const TAU: Scalar = Scalar::from_raw(1608);
+
+/// Penetration depth, in pixel, allowed before pushing overlapping objects apart.
+/// Keeping a small overlap avoid jittering on resting contacts.
+const PENETRATION_SLOP: Scalar = Scalar::from_raw(1 << 6);
+/// Part of the penetration resolved on each collision.
+const PENETRATION_CORRECTION_PERCENT: Scalar = Scalar::from_raw(204);
+
+fn init_physic_object(
+    trigger: Trigger<OnInsert, PhysicObject>,
+    mut physic_objects: Query<(&mut PhysicObject, &Transform, Option<&CircleCollider>)>,
+) {
+    let Ok((mut object, transform, collider)) = physic_objects.get_mut(trigger.target()) else {
+        return;
+    };
+
+    object.position = from_vec2(transform.translation.truncate());
+
+    if let Some(collider) = collider
+        && object.inertia == Scalar::new(0)
+    {
+        let radius = collider.radius();
+        object.inertia = object.mass * radius * radius / 2;
+    }
+}
 
 fn move_physic_objects(
-    time: Res<Time<Fixed>>,
-    mut physic_objects: Query<(&mut PhysicObject, &mut Transform)>,
+    step: Res<PhysicStep>,
+    config: Res<PhysicConfig>,
+    mut sweep_targets: Local<Vec<SweepTarget>>,
+    obstacles: Obstacles,
+    zones: Query<&PhysicZone>,
+    mut physic_objects: Query<(
+        Entity,
+        &mut PhysicObject,
+        Option<(&CircleCollider, &CollisionLayers)>,
+        Has<Sensor>,
+    )>,
 ) {
-    let elapsed = time.delta_secs();
-    for (mut object, mut transform) in &mut physic_objects {
+    sweep_targets.clear();
+    sweep_targets.extend(
+        physic_objects
+            .iter()
+            // Sensors don't stop anything, so nothing is swept against them
+            .filter(|(_, object, _, is_sensor)| object.enable && !is_sensor)
+            .filter_map(|(entity, object, collider, _)| {
+                collider.map(|(collider, layers)| SweepTarget {
+                    entity,
+                    center: collider.center(object),
+                    radius: collider.radius(),
+                    layers: *layers,
+                })
+            }),
+    );
+
+    for (entity, mut object, collider, _) in &mut physic_objects {
         if !object.enable {
             continue;
         }
 
-        let impulse = object.impulse;
-        let mass = object.mass;
-        let velocity = object.velocity;
-        let drag = object.drag;
-        object.velocity += impulse / mass - (velocity * drag) * elapsed;
+        match object.kind {
+            RigidBodyKind::Static => {
+                object.impulse = Vector::default();
+                object.angular_impulse = Scalar::new(0);
+                continue;
+            }
+            RigidBodyKind::Kinematic => {
+                let velocity = object.velocity;
+                object.position += step.scale(velocity);
+                object.integrate_rotation(&step);
+                object.impulse = Vector::default();
+                object.angular_impulse = Scalar::new(0);
+                continue;
+            }
+            RigidBodyKind::Dynamic => {}
+        }
+
+        if object.sleeping {
+            if object.impulse == Vector::default() && object.angular_impulse == Scalar::new(0) {
+                continue;
+            }
+            object.wake_up();
+        }
+
+        let center = collider.map_or(object.position, |(collider, _)| collider.center(&object));
+        let zone = ZoneInfluence::at(center, &zones);
 
-        transform.translation += (object.velocity * elapsed).extend(0.);
+        let mut displacement = object.integrate_velocity(&step, &zone, config.spin_curve);
 
-        if object.velocity.length_squared() < SPEED_SQUARED_TO_ZERO {
-            object.velocity = Vec2::ZERO;
+        if let Some((collider, layers)) = collider {
+            let radius = collider.radius();
+            if object.ccd || ccd::needs_sweep(displacement, radius) {
+                displacement = ccd::sweep(
+                    entity,
+                    collider.center(&object),
+                    radius,
+                    layers,
+                    displacement,
+                    &sweep_targets,
+                    &obstacles,
+                    &config.boundary,
+                );
+            }
         }
 
-        object.impulse = Vec2::ZERO;
+        object.advance(displacement, &step, config.sleep_steps);
     }
 }
 
+/// Copy the simulated positions to the `Transform`, and the `GlobalTransform` of root
+/// objects, so gameplay code see them without waiting for the transform propagation.
+fn sync_transforms(
+    mut physic_objects: Query<(
+        &mut PhysicObject,
+        &mut Transform,
+        &mut GlobalTransform,
+        Has<ChildOf>,
+    )>,
+) {
+    for (mut object, mut transform, mut global_transform, has_parent) in &mut physic_objects {
+        if object.enable {
+            let position = to_vec2(object.position);
+
+            // The renderer use the z angle of the rotation as a fraction of a turn
+            let mut turn = object.rotation;
+            if turn > num!(0.5) {
+                turn -= Scalar::new(1);
+            }
+            let rotation = Quat::from_rotation_z(scalar_to_f32(turn));
+
+            if transform.translation.truncate() != position || transform.rotation != rotation {
+                transform.translation = position.extend(transform.translation.z);
+                transform.rotation = rotation;
+
+                if !has_parent {
+                    *global_transform = GlobalTransform::from(*transform);
+                }
+            }
+        } else {
+            object.position = from_vec2(transform.translation.truncate());
+        }
+    }
+}
+
+/// Triggered on both objects every frame they overlap, used to resolve the collision.
+///
+/// Gameplay reacting once per hit should use `CollisionStarted` instead.
 #[derive(Event, Copy, Clone)]
 pub struct Collision {
     pub entity1: Entity,
@@ -128,88 +696,296 @@ impl Collision {
     }
 }
 
-pub fn detect_collision(
-    mut commands: Commands,
-    collider_query: Query<(Entity, &PhysicObject, &CircleCollider, &GlobalTransform)>,
+pub fn update_broad_phase(
+    config: Res<PhysicConfig>,
+    mut grid: ResMut<BroadPhaseGrid>,
+    collider_query: Query<(Entity, &PhysicObject, &CircleCollider, &CollisionLayers)>,
 ) {
-    let colliders: Vec<(Entity, &PhysicObject, &CircleCollider, &GlobalTransform)> =
-        collider_query.iter().collect();
+    grid.clear(&config.boundary);
 
-    for (entity, object, collider, transform) in &colliders {
+    for (entity, object, collider, layers) in &collider_query {
         if !object.enable {
             continue;
         }
 
-        for (other_entity, other_object, other_collider, other_transform) in &colliders {
-            if entity == other_entity {
-                break;
-            }
+        grid.insert(
+            entity,
+            collider.center(object),
+            collider.radius(),
+            *layers,
+            object.is_awake(),
+        );
+    }
+}
 
-            if !other_object.enable {
-                continue;
-            }
+pub fn detect_collision(
+    mut commands: Commands,
+    grid: Res<BroadPhaseGrid>,
+    mut contacts: ResMut<ContactCache>,
+    mut overlaps: ResMut<SensorCache>,
+    collider_query: Query<&PhysicObject>,
+    sensors: Query<(), With<Sensor>>,
+) {
+    grid.for_each_pair(|body, other| {
+        let distance_for_collision = body.radius + other.radius;
+        let distance_squared_for_collision = distance_for_collision * distance_for_collision;
+        let distance_squared = (body.center - other.center).magnitude_squared();
 
-            let distance_for_collision = (collider.radius + other_collider.radius) as f32;
-            let distance_squared_for_collision = distance_for_collision * distance_for_collision;
-            let distance_squared = collider
-                .center(transform)
-                .distance_squared(other_collider.center(other_transform));
-
-            if distance_squared < distance_squared_for_collision {
-                let collision = Collision {
-                    entity1: *entity,
-                    entity2: *other_entity,
-                };
-                commands.trigger_targets(collision, [entity.clone(), other_entity.clone()]);
+        if distance_squared < distance_squared_for_collision {
+            match (
+                sensors.contains(body.entity),
+                sensors.contains(other.entity),
+            ) {
+                (false, false) => {}
+                (true, false) => {
+                    overlaps.add(body.entity, other.entity);
+                    return;
+                }
+                (false, true) => {
+                    overlaps.add(other.entity, body.entity);
+                    return;
+                }
+                // Sensors don't detect each other
+                (true, true) => return,
             }
+
+            let velocity = |entity| {
+                collider_query
+                    .get(entity)
+                    .map_or(Vector::default(), |object| object.velocity)
+            };
+
+            let delta = other.center - body.center;
+            let normal =
+                direction_and_length(delta).map_or(Vector::default(), |(normal, _)| normal);
+            contacts.add(Contact::new(
+                body.entity,
+                other.entity,
+                normal,
+                velocity(other.entity) - velocity(body.entity),
+            ));
+
+            let collision = Collision {
+                entity1: body.entity,
+                entity2: other.entity,
+            };
+            commands.trigger_targets(collision, [body.entity, other.entity]);
         }
-    }
+    });
 }
 
-fn bounce(physic_object: &mut PhysicObject, normal: Vec2) {
+/// Tangent of the contact, oriented along the relative velocity.
+fn contact_tangent(relative_velocity: Vector, normal: Vector) -> Option<Vector> {
+    let tangent = relative_velocity - normal * relative_velocity.dot(normal);
+    direction_and_length(tangent).map(|(tangent, _)| tangent)
+}
+
+fn bounce(
+    physic_object: &mut PhysicObject,
+    normal: Vector,
+    radius: Scalar,
+    restitution: Scalar,
+    friction: Scalar,
+) {
     let velocity = physic_object.velocity;
 
     let velocity_along_normal = velocity.dot(normal);
-    if velocity_along_normal >= 0. {
+    if velocity_along_normal >= Scalar::new(0) {
         // Nothing to do since the object is already going in the right direction
         return;
     }
 
-    let impulse = -2. * velocity_along_normal * physic_object.mass * normal;
-    physic_object.impulse += impulse;
+    let j = -(Scalar::new(1) + restitution) * velocity_along_normal;
+    physic_object.impulse += normal * (j * physic_object.mass);
+
+    // The object touch the wall on the side opposite to the normal
+    let contact = normal * -radius;
+    let contact_velocity = velocity + perpendicular(contact) * physic_object.angular_velocity;
+
+    let Some(tangent) = contact_tangent(contact_velocity, normal) else {
+        return;
+    };
+
+    let arm = cross(contact, tangent);
+    let effective_inverse_mass =
+        physic_object.inverse_mass() + arm * arm * physic_object.inverse_inertia();
+
+    // Coulomb friction: the wall can't push more than `friction` times the bounce
+    let max_friction = j * physic_object.mass * friction;
+    let jt = (-contact_velocity.dot(tangent) / effective_inverse_mass)
+        .clamp(-max_friction, max_friction);
+
+    physic_object.impulse += tangent * jt;
+    physic_object.angular_impulse += arm * jt;
 }
 
+/// Triggered on an object leaving `PhysicConfig::boundary` through a gap. The object is
+/// disabled.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LeftBoundary;
+
 fn keep_object_in_boundary(
+    mut commands: Commands,
     config: Res<PhysicConfig>,
-    collider_query: Query<(&mut PhysicObject, &CircleCollider, &GlobalTransform)>,
+    collider_query: Query<(Entity, &mut PhysicObject, &CircleCollider, &PhysicMaterial)>,
+) {
+    let boundary = &config.boundary;
+
+    for (entity, mut physic_object, collider, material) in collider_query {
+        if !physic_object.is_dynamic() || !physic_object.is_awake() {
+            continue;
+        }
+
+        let center = collider.center(&physic_object);
+        let radius = collider.radius();
+
+        if boundary.is_out(center, radius) {
+            physic_object.enable = false;
+            commands.trigger_targets(LeftBoundary, entity);
+            continue;
+        }
+
+        collide_with_boundary(&mut physic_object, center, radius, material, &config);
+    }
+}
+
+/// Push a circle at `center` back inside the boundary and bounce it on the walls it
+/// touches. Returns whether it touched one.
+fn collide_with_boundary(
+    physic_object: &mut PhysicObject,
+    center: Vector,
+    radius: Scalar,
+    material: &PhysicMaterial,
+    config: &PhysicConfig,
+) -> bool {
+    let restitution = material.combined_restitution(&config.wall_material);
+    let friction = material.combined_friction(&config.wall_material);
+    let mut touched = false;
+
+    // Walls don't move, so the object is pushed back inside the boundary completely.
+    config
+        .boundary
+        .for_each_contact(center, radius, |normal, penetration| {
+            physic_object.position += normal * penetration;
+            bounce(physic_object, normal, radius, restitution, friction);
+            touched = true;
+        });
+
+    touched
+}
+
+type ObstacleQuery<'w, 's, T> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static T,
+        &'static PhysicMaterial,
+        &'static CollisionLayers,
+        Has<Sensor>,
+    ),
+>;
+
+fn collide_with_obstacles(
+    mut commands: Commands,
+    mut contacts: ResMut<ContactCache>,
+    mut overlaps: ResMut<SensorCache>,
+    collider_query: Query<
+        (
+            Entity,
+            &mut PhysicObject,
+            &CircleCollider,
+            &PhysicMaterial,
+            &CollisionLayers,
+        ),
+        Without<Sensor>,
+    >,
+    boxes: ObstacleQuery<BoxCollider>,
+    segments: ObstacleQuery<SegmentCollider>,
+    polygons: ObstacleQuery<PolygonCollider>,
 ) {
-    let left = config.boundary.min.x;
-    let top = config.boundary.min.y;
-    let right = config.boundary.max.x;
-    let bottom = config.boundary.max.y;
+    if boxes.is_empty() && segments.is_empty() && polygons.is_empty() {
+        return;
+    }
 
-    for (mut physic_object, collider, transform) in collider_query {
-        if !physic_object.enable {
+    for (entity, mut physic_object, collider, material, layers) in collider_query {
+        if !physic_object.is_dynamic() || !physic_object.is_awake() {
             continue;
         }
 
-        let position = collider.center(transform);
-        let radius = collider.radius as f32;
+        let mut collide = |obstacle: Entity,
+                           shape: &dyn ObstacleShape,
+                           obstacle_material: &PhysicMaterial,
+                           obstacle_layers: &CollisionLayers,
+                           is_sensor: bool| {
+            if !layers.interacts_with(obstacle_layers) {
+                return;
+            }
+
+            let Some((normal, penetration)) =
+                obstacle::circle_contact(shape, collider.center(&physic_object), collider.radius())
+            else {
+                return;
+            };
+
+            if is_sensor {
+                overlaps.add(obstacle, entity);
+                return;
+            }
+
+            // `normal` goes from the obstacle to the object
+            contacts.add(Contact::new(
+                entity,
+                obstacle,
+                -normal,
+                -physic_object.velocity,
+            ));
+
+            // Obstacles don't move, so the object is pushed out of it completely.
+            physic_object.position += normal * penetration;
+            bounce(
+                &mut physic_object,
+                normal,
+                collider.radius(),
+                material.combined_restitution(obstacle_material),
+                material.combined_friction(obstacle_material),
+            );
 
-        if position.y - radius < top {
-            // The circle is hiting the top side
-            bounce(&mut physic_object, Vec2::Y);
-        } else if position.y + radius > bottom {
-            // The circle is hiting the bottom side
-            bounce(&mut physic_object, Vec2::NEG_Y);
+            let collision = Collision {
+                entity1: entity,
+                entity2: obstacle,
+            };
+            commands.trigger_targets(collision, [entity, obstacle]);
+        };
+
+        for (obstacle, shape, obstacle_material, obstacle_layers, is_sensor) in &boxes {
+            collide(
+                obstacle,
+                shape,
+                obstacle_material,
+                obstacle_layers,
+                is_sensor,
+            );
+        }
+
+        for (obstacle, shape, obstacle_material, obstacle_layers, is_sensor) in &segments {
+            collide(
+                obstacle,
+                shape,
+                obstacle_material,
+                obstacle_layers,
+                is_sensor,
+            );
         }
 
-        if position.x - radius < left {
-            // The circle is hiting the left side
-            bounce(&mut physic_object, Vec2::X);
-        } else if position.x + radius > right {
-            // The circle is hiting the right side
-            bounce(&mut physic_object, Vec2::NEG_X);
+        for (obstacle, shape, obstacle_material, obstacle_layers, is_sensor) in &polygons {
+            collide(
+                obstacle,
+                shape,
+                obstacle_material,
+                obstacle_layers,
+                is_sensor,
+            );
         }
     }
 }
@@ -217,7 +993,7 @@ fn keep_object_in_boundary(
 // https://code.tutsplus.com/how-to-create-a-custom-2d-physics-engine-the-basics-and-impulse-resolution--gamedev-6331t
 fn handle_collision(
     collision: Trigger<Collision>,
-    mut physic_objects: Query<(&mut PhysicObject, &GlobalTransform)>,
+    mut physic_objects: Query<(&mut PhysicObject, &CircleCollider, &PhysicMaterial)>,
 ) -> Result {
     let target = collision.target();
     let entity1 = collision.entity1;
@@ -229,33 +1005,88 @@ fn handle_collision(
         return Ok(());
     }
 
-    let [(mut po1, gt1), (mut po2, gt2)] = physic_objects.get_many_mut([entity1, entity2])?;
+    if !physic_objects.contains(entity2) {
+        // Collisions with obstacles are already resolved by `collide_with_obstacles`
+        return Ok(());
+    }
 
-    let t1 = gt1.translation();
-    let t2 = gt2.translation();
+    let [(mut po1, c1, mat1), (mut po2, c2, mat2)] =
+        physic_objects.get_many_mut([entity1, entity2])?;
 
-    let normal = (t2 - t1).normalize().truncate();
+    let delta = c2.center(&po2) - c1.center(&po1);
+    let Some((normal, distance)) = direction_and_length(delta) else {
+        // Perfectly stacked objects don't have a collision normal
+        return Ok(());
+    };
 
     let rv = po2.velocity - po1.velocity;
 
     let vel_along_normal = normal.dot(rv);
 
-    if vel_along_normal > 0. {
+    // Only an object hitting fast enough wakes up a sleeping one. Slower ones see it as
+    // static, so objects settling against each other can still fall asleep.
+    if vel_along_normal * vel_along_normal >= SLEEP_SPEED_SQUARED {
+        po1.wake_up();
+        po2.wake_up();
+    }
+
+    let m1 = po1.inverse_mass();
+    let m2 = po2.inverse_mass();
+
+    if m1 + m2 == Scalar::new(0) {
+        // Neither object can be pushed
         return Ok(());
     }
 
-    let m1 = 1. / po1.mass;
-    let m2 = 1. / po2.mass;
+    // Positional correction so the objects don't sink into each other.
+    let penetration = c1.radius() + c2.radius() - distance;
+    if penetration > PENETRATION_SLOP {
+        let correction = normal
+            * ((penetration - PENETRATION_SLOP) / (m1 + m2) * PENETRATION_CORRECTION_PERCENT);
+
+        po1.position -= correction * m1;
+        po2.position += correction * m2;
+    }
+
+    if vel_along_normal > Scalar::new(0) {
+        return Ok(());
+    }
 
-    let e: f32 = 1.;
+    let e = mat1.combined_restitution(mat2);
 
-    let mut j = -(1. + e) * vel_along_normal;
+    let mut j = -(Scalar::new(1) + e) * vel_along_normal;
     j /= m1 + m2;
 
-    let impulse = j * normal;
+    let impulse = normal * j;
+
+    po1.impulse -= impulse * m1;
+    po2.impulse += impulse * m2;
+
+    // Friction at the contact point, which also transfers spin between the objects.
+    let contact1 = normal * c1.radius();
+    let contact2 = normal * -c2.radius();
+    let contact_rv = po2.velocity + perpendicular(contact2) * po2.angular_velocity
+        - po1.velocity
+        - perpendicular(contact1) * po1.angular_velocity;
+
+    let Some(tangent) = contact_tangent(contact_rv, normal) else {
+        return Ok(());
+    };
+
+    let arm1 = cross(contact1, tangent);
+    let arm2 = cross(contact2, tangent);
+    let effective_inverse_mass =
+        m1 + m2 + arm1 * arm1 * po1.inverse_inertia() + arm2 * arm2 * po2.inverse_inertia();
+
+    let max_friction = j * mat1.combined_friction(mat2);
+    let jt = (-contact_rv.dot(tangent) / effective_inverse_mass).clamp(-max_friction, max_friction);
+
+    let friction_impulse = tangent * jt;
 
-    po1.impulse -= m1 * impulse;
-    po2.impulse += m2 * impulse;
+    po1.impulse -= friction_impulse * m1;
+    po2.impulse += friction_impulse * m2;
+    po1.angular_impulse -= arm1 * jt;
+    po2.angular_impulse += arm2 * jt;
 
     Ok(())
 }
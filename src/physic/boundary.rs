@@ -0,0 +1,324 @@
+use alloc::vec::Vec;
+use bevy::prelude::*;
+
+use super::{Scalar, Vector, direction_and_length, perpendicular};
+
+/// Arena the objects are kept in.
+///
+/// `min` and `max` are the bounding box of the arena, and the arena itself when the shape
+/// is `BoundaryShape::Rect`. Objects going through one of the `gaps` leave the arena.
+#[derive(Clone, Default, Debug)]
+pub struct Boundary {
+    pub min: Vector,
+    pub max: Vector,
+    pub shape: BoundaryShape,
+    pub gaps: Vec<BoundaryGap>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub enum BoundaryShape {
+    #[default]
+    Rect,
+    Circle {
+        center: Vector,
+        radius: Scalar,
+    },
+    /// Convex polygon, the points can be in either winding order.
+    Polygon(Vec<Vector>),
+}
+
+/// Opening in the wall of the arena. Its part of the wall is the one closer than
+/// `half_width` to `center`.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundaryGap {
+    pub center: Vector,
+    pub half_width: Scalar,
+}
+
+/// One side of the arena.
+#[derive(Clone, Copy)]
+enum Wall {
+    /// Straight wall going through `point`, with `normal` pointing inside the arena.
+    Line {
+        point: Vector,
+        normal: Vector,
+    },
+    Circle {
+        center: Vector,
+        radius: Scalar,
+    },
+}
+
+impl Wall {
+    /// Closest point of the wall, normal toward the inside and distance of `point` to the
+    /// wall, negative when it is on the outside.
+    fn distance(&self, point: Vector) -> Option<(Vector, Vector, Scalar)> {
+        match *self {
+            Self::Line {
+                point: on_wall,
+                normal,
+            } => {
+                let distance = (point - on_wall).dot(normal);
+                Some((point - normal * distance, normal, distance))
+            }
+            Self::Circle { center, radius } => {
+                let (direction, length) = direction_and_length(point - center)?;
+                Some((center + direction * radius, -direction, radius - length))
+            }
+        }
+    }
+
+    /// Fraction of `displacement` after which a circle at `center`, inside the arena,
+    /// touches the wall.
+    fn time_of_impact(
+        &self,
+        center: Vector,
+        radius: Scalar,
+        displacement: Vector,
+    ) -> Option<Scalar> {
+        let zero = Scalar::new(0);
+
+        match *self {
+            Self::Line { point, normal } => {
+                let distance = (center - point).dot(normal);
+                let approach = displacement.dot(normal);
+                (distance >= radius && approach < zero).then(|| (distance - radius) / -approach)
+            }
+            Self::Circle {
+                center: wall_center,
+                radius: wall_radius,
+            } => {
+                // The circle stays inside while its center is closer than this
+                let reach = wall_radius - radius;
+                let offset = center - wall_center;
+
+                let c = offset.magnitude_squared() - reach * reach;
+                let a = displacement.magnitude_squared();
+                if c > zero || a == zero {
+                    return None;
+                }
+
+                let b = offset.dot(displacement);
+                Some((-b + (b * b - a * c).sqrt()) / a)
+            }
+        }
+    }
+}
+
+impl Boundary {
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self {
+            min: Vector::new(Scalar::new(x0), Scalar::new(y0)),
+            max: Vector::new(Scalar::new(x1), Scalar::new(y1)),
+            ..default()
+        }
+    }
+
+    pub fn circle(center: Vector, radius: Scalar) -> Self {
+        let extent = Vector::new(radius, radius);
+        Self {
+            min: center - extent,
+            max: center + extent,
+            shape: BoundaryShape::Circle { center, radius },
+            ..default()
+        }
+    }
+
+    pub fn polygon(points: impl IntoIterator<Item = Vector>) -> Self {
+        let points: Vec<Vector> = points.into_iter().collect();
+
+        let mut min = points.first().copied().unwrap_or_default();
+        let mut max = min;
+        for point in &points {
+            min = Vector::new(min.x.min(point.x), min.y.min(point.y));
+            max = Vector::new(max.x.max(point.x), max.y.max(point.y));
+        }
+
+        Self {
+            min,
+            max,
+            shape: BoundaryShape::Polygon(points),
+            ..default()
+        }
+    }
+
+    pub fn with_gap(mut self, center: Vector, half_width: Scalar) -> Self {
+        self.gaps.push(BoundaryGap { center, half_width });
+        self
+    }
+
+    fn for_each_wall(&self, mut f: impl FnMut(Wall)) {
+        let zero = Scalar::new(0);
+        let one = Scalar::new(1);
+
+        match &self.shape {
+            BoundaryShape::Rect => {
+                f(Wall::Line {
+                    point: self.min,
+                    normal: Vector::new(one, zero),
+                });
+                f(Wall::Line {
+                    point: self.min,
+                    normal: Vector::new(zero, one),
+                });
+                f(Wall::Line {
+                    point: self.max,
+                    normal: Vector::new(-one, zero),
+                });
+                f(Wall::Line {
+                    point: self.max,
+                    normal: Vector::new(zero, -one),
+                });
+            }
+            BoundaryShape::Circle { center, radius } => f(Wall::Circle {
+                center: *center,
+                radius: *radius,
+            }),
+            BoundaryShape::Polygon(points) => {
+                // The average of the points of a convex polygon is inside it, unlike the
+                // center of its bounding box which can be on a side, as with right triangles
+                let inside = points
+                    .iter()
+                    .fold(Vector::default(), |sum, point| sum + *point)
+                    / points.len().max(1) as i32;
+
+                let edges = points.iter().zip(points.iter().cycle().skip(1));
+                for (&start, &end) in edges {
+                    let edge_normal = perpendicular(end - start);
+                    let Some((mut normal, _)) = direction_and_length(edge_normal) else {
+                        continue;
+                    };
+                    if (inside - start).dot(normal) < zero {
+                        normal = -normal;
+                    }
+                    f(Wall::Line {
+                        point: start,
+                        normal,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Call `f` with points spread along the walls, used to draw them.
+    pub fn for_each_outline_point(&self, mut f: impl FnMut(Vector)) {
+        match &self.shape {
+            BoundaryShape::Rect => {
+                let middle = (self.min + self.max) / 2;
+                let corners = [
+                    self.min,
+                    Vector::new(self.max.x, self.min.y),
+                    self.max,
+                    Vector::new(self.min.x, self.max.y),
+                ];
+
+                for corner in corners {
+                    f(corner);
+                }
+                f(Vector::new(middle.x, self.min.y));
+                f(Vector::new(self.max.x, middle.y));
+                f(Vector::new(middle.x, self.max.y));
+                f(Vector::new(self.min.x, middle.y));
+            }
+            BoundaryShape::Circle { center, radius } => {
+                let diagonal = Scalar::from_raw(181);
+                let zero = Scalar::new(0);
+                let one = Scalar::new(1);
+                let directions = [
+                    Vector::new(one, zero),
+                    Vector::new(diagonal, diagonal),
+                    Vector::new(zero, one),
+                    Vector::new(-diagonal, diagonal),
+                    Vector::new(-one, zero),
+                    Vector::new(-diagonal, -diagonal),
+                    Vector::new(zero, -one),
+                    Vector::new(diagonal, -diagonal),
+                ];
+
+                for direction in directions {
+                    f(*center + direction * *radius);
+                }
+            }
+            BoundaryShape::Polygon(points) => {
+                let edges = points.iter().zip(points.iter().cycle().skip(1));
+                for (&start, &end) in edges {
+                    f(start);
+                    f((start + end) / 2);
+                }
+            }
+        }
+    }
+
+    fn is_gap(&self, point: Vector) -> bool {
+        self.gaps
+            .iter()
+            .any(|gap| (point - gap.center).magnitude_squared() <= gap.half_width * gap.half_width)
+    }
+
+    /// Call `f` with the normal, pointing inside, and the penetration of every wall a
+    /// circle is going through. Gaps are skipped.
+    pub fn for_each_contact(
+        &self,
+        center: Vector,
+        radius: Scalar,
+        mut f: impl FnMut(Vector, Scalar),
+    ) {
+        self.for_each_wall(|wall| {
+            let Some((on_wall, normal, distance)) = wall.distance(center) else {
+                return;
+            };
+
+            if distance < radius && !self.is_gap(on_wall) {
+                f(normal, radius - distance);
+            }
+        });
+    }
+
+    /// Has a circle left the arena, either with its center through a gap or by being
+    /// completely outside.
+    pub fn is_out(&self, center: Vector, radius: Scalar) -> bool {
+        let zero = Scalar::new(0);
+        let mut out = false;
+
+        self.for_each_wall(|wall| {
+            let Some((on_wall, _, distance)) = wall.distance(center) else {
+                return;
+            };
+
+            out |= distance <= -radius || (distance < zero && self.is_gap(on_wall));
+        });
+
+        out
+    }
+
+    /// First wall hit by a circle moving from `center` by `displacement`, as a fraction of
+    /// `displacement`, and the normal of the wall. Gaps aren't hit.
+    pub fn time_of_impact(
+        &self,
+        center: Vector,
+        radius: Scalar,
+        displacement: Vector,
+    ) -> Option<(Scalar, Vector)> {
+        let mut first_impact: Option<(Scalar, Vector)> = None;
+
+        self.for_each_wall(|wall| {
+            let Some(t) = wall.time_of_impact(center, radius, displacement) else {
+                return;
+            };
+
+            if first_impact.is_some_and(|(first, _)| first <= t) {
+                return;
+            }
+
+            let Some((on_wall, normal, _)) = wall.distance(center + displacement * t) else {
+                return;
+            };
+
+            if !self.is_gap(on_wall) {
+                first_impact = Some((t, normal));
+            }
+        });
+
+        first_impact
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+use bevy::prelude::*;
+
+use super::{Boundary, CollisionLayers, Scalar, Vector};
+
+/// Size in pixel of a cell of the grid. It matches the diameter of the biggest ball.
+const CELL_SIZE: i32 = 32;
+
+#[derive(Clone, Copy)]
+pub struct BroadPhaseBody {
+    pub entity: Entity,
+    pub center: Vector,
+    pub radius: Scalar,
+    pub layers: CollisionLayers,
+    /// Static and sleeping bodies aren't awake.
+    pub awake: bool,
+    min_cell: (usize, usize),
+    max_cell: (usize, usize),
+}
+
+/// Uniform grid covering `PhysicConfig::boundary` used to find the pairs of bodies
+/// that could collide without testing every pair.
+///
+/// The buffers are kept between frames so that the grid doesn't allocate once it has
+/// reached the size needed by the level.
+#[derive(Resource, Default)]
+pub struct BroadPhaseGrid {
+    origin: Vector,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    bodies: Vec<BroadPhaseBody>,
+}
+
+impl BroadPhaseGrid {
+    pub fn clear(&mut self, boundary: &Boundary) {
+        let size = boundary.max - boundary.min;
+        let columns = (size.x.floor().max(0) / CELL_SIZE) as usize + 1;
+        let rows = (size.y.floor().max(0) / CELL_SIZE) as usize + 1;
+
+        self.origin = boundary.min;
+        self.columns = columns;
+        self.rows = rows;
+        self.cells.resize_with(columns * rows, Vec::new);
+
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+        self.bodies.clear();
+    }
+
+    fn cell_of(&self, point: Vector) -> (usize, usize) {
+        let local = point - self.origin;
+        let column = (local.x.floor() / CELL_SIZE).clamp(0, self.columns as i32 - 1);
+        let row = (local.y.floor() / CELL_SIZE).clamp(0, self.rows as i32 - 1);
+        (column as usize, row as usize)
+    }
+
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        center: Vector,
+        radius: Scalar,
+        layers: CollisionLayers,
+        awake: bool,
+    ) {
+        let extent = Vector::new(radius, radius);
+        let min_cell = self.cell_of(center - extent);
+        let max_cell = self.cell_of(center + extent);
+
+        let index = self.bodies.len();
+        self.bodies.push(BroadPhaseBody {
+            entity,
+            center,
+            radius,
+            layers,
+            awake,
+            min_cell,
+            max_cell,
+        });
+
+        for row in min_cell.1..=max_cell.1 {
+            for column in min_cell.0..=max_cell.0 {
+                self.cells[row * self.columns + column].push(index);
+            }
+        }
+    }
+
+    /// Call `f` once for every pair of bodies sharing at least one cell and whose
+    /// layers interact. Pairs where neither body is awake are skipped.
+    ///
+    /// The first body of the pair is always the one inserted last.
+    pub fn for_each_pair(&self, mut f: impl FnMut(&BroadPhaseBody, &BroadPhaseBody)) {
+        for (cell_index, cell) in self.cells.iter().enumerate() {
+            let cell_position = (cell_index % self.columns, cell_index / self.columns);
+
+            for (i, &other_index) in cell.iter().enumerate() {
+                for &index in &cell[i + 1..] {
+                    let body = &self.bodies[index];
+                    let other = &self.bodies[other_index];
+
+                    // Bodies can share many cells, only report the pair in the first one.
+                    let first_shared_cell = (
+                        body.min_cell.0.max(other.min_cell.0),
+                        body.min_cell.1.max(other.min_cell.1),
+                    );
+                    if first_shared_cell != cell_position
+                        || !body.layers.interacts_with(&other.layers)
+                        || (!body.awake && !other.awake)
+                    {
+                        continue;
+                    }
+
+                    f(body, other);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use super::{
+    Boundary, CollisionLayers, Scalar, Vector, direction_and_length,
+    obstacle::{self, Obstacles},
+};
+
+/// How far, in pixel, a swept object is allowed to go past the point of contact.
+/// The overlap is needed so the discrete collision detection pick up the contact.
+const CCD_SKIN: Scalar = Scalar::from_raw(1 << 5);
+
+/// Collider as seen by the sweep, at the start of the physic step.
+#[derive(Clone, Copy)]
+pub struct SweepTarget {
+    pub entity: Entity,
+    pub center: Vector,
+    pub radius: Scalar,
+    pub layers: CollisionLayers,
+}
+
+/// Should an object moving by `displacement` in one step be swept to avoid tunneling.
+pub fn needs_sweep(displacement: Vector, radius: Scalar) -> bool {
+    let half_radius = radius / 2;
+    displacement.magnitude_squared() > half_radius * half_radius
+}
+
+/// Fraction of `displacement` after which a circle at `center` touches the other circle.
+fn time_of_impact_circle(
+    center: Vector,
+    radius: Scalar,
+    displacement: Vector,
+    other_center: Vector,
+    other_radius: Scalar,
+) -> Option<Scalar> {
+    let zero = Scalar::new(0);
+
+    let offset = center - other_center;
+    let distance = radius + other_radius;
+
+    let c = offset.magnitude_squared() - distance * distance;
+    if c < zero {
+        // Already overlapping, the discrete collision will handle it
+        return None;
+    }
+
+    let b = offset.dot(displacement);
+    if b >= zero {
+        // Moving away from the other circle
+        return None;
+    }
+
+    let a = displacement.magnitude_squared();
+    let discriminant = b * b - a * c;
+    if a == zero || discriminant < zero {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / a;
+    (t <= Scalar::new(1)).then_some(t.max(zero))
+}
+
+/// Shorten `displacement` so a circle moving from `center` stops at its first contact
+/// with another collider, an obstacle or the boundary instead of going through it.
+#[allow(clippy::too_many_arguments)]
+pub fn sweep(
+    entity: Entity,
+    center: Vector,
+    radius: Scalar,
+    layers: &CollisionLayers,
+    displacement: Vector,
+    targets: &[SweepTarget],
+    obstacles: &Obstacles,
+    boundary: &Boundary,
+) -> Vector {
+    let mut first_impact = boundary
+        .time_of_impact(center, radius, displacement)
+        .map(|(t, _)| t)
+        .filter(|t| *t <= Scalar::new(1));
+
+    for target in targets {
+        if target.entity == entity || !layers.interacts_with(&target.layers) {
+            continue;
+        }
+
+        let Some(t) =
+            time_of_impact_circle(center, radius, displacement, target.center, target.radius)
+        else {
+            continue;
+        };
+
+        if first_impact.is_none_or(|first| t < first) {
+            first_impact = Some(t);
+        }
+    }
+
+    let movement = direction_and_length(displacement);
+
+    if let Some((direction, travel)) = movement {
+        obstacles.for_each(|shape, obstacle_layers| {
+            if !layers.interacts_with(obstacle_layers) {
+                return;
+            }
+
+            if let Some((distance, _)) = obstacle::cast_circle(shape, center, radius, direction)
+                && distance <= travel
+            {
+                let t = distance / travel;
+                if first_impact.is_none_or(|first| t < first) {
+                    first_impact = Some(t);
+                }
+            }
+        });
+    }
+
+    let Some(t) = first_impact else {
+        return displacement;
+    };
+
+    let Some((direction, travel)) = movement else {
+        // Moving less than the fixed point precision, there is no room for a skin
+        return displacement * t;
+    };
+    let skin = CCD_SKIN.min(travel - travel * t);
+    displacement * t + direction * skin
+}
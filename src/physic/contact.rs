@@ -0,0 +1,128 @@
+use alloc::vec::Vec;
+use bevy::prelude::*;
+
+use super::{PhysicObject, Scalar, Vector};
+
+/// Two objects touching each other.
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub entity1: Entity,
+    pub entity2: Entity,
+    /// Direction from `entity1` toward `entity2`.
+    pub normal: Vector,
+    /// Speed at which the objects are getting closer along the normal.
+    pub impact_speed: Scalar,
+}
+
+impl Contact {
+    /// `relative_velocity` is the velocity of `entity2` minus the one of `entity1`.
+    pub fn new(
+        entity1: Entity,
+        entity2: Entity,
+        normal: Vector,
+        relative_velocity: Vector,
+    ) -> Self {
+        Self {
+            entity1,
+            entity2,
+            normal,
+            impact_speed: (-relative_velocity.dot(normal)).max(Scalar::new(0)),
+        }
+    }
+
+    pub fn self_and_other(&self, target: &Entity) -> Option<(Entity, Entity)> {
+        if *target == self.entity1 {
+            Some((self.entity1, self.entity2))
+        } else if *target == self.entity2 {
+            Some((self.entity2, self.entity1))
+        } else {
+            None
+        }
+    }
+
+    fn is_same_pair(&self, other: &Self) -> bool {
+        (self.entity1 == other.entity1 && self.entity2 == other.entity2)
+            || (self.entity1 == other.entity2 && self.entity2 == other.entity1)
+    }
+}
+
+/// Triggered on both objects the first frame they touch.
+#[derive(Event, Clone, Copy, Debug, Deref)]
+pub struct CollisionStarted(pub Contact);
+
+/// Triggered on both objects every following frame they keep touching.
+#[derive(Event, Clone, Copy, Debug, Deref)]
+pub struct CollisionStay(pub Contact);
+
+/// Triggered on both objects the first frame they stop touching, with the last contact.
+#[derive(Event, Clone, Copy, Debug, Deref)]
+pub struct CollisionEnded(pub Contact);
+
+/// Contacts of the current and previous frame, used to know when contacts start and end.
+#[derive(Resource, Default)]
+pub struct ContactCache {
+    previous: Vec<Contact>,
+    current: Vec<Contact>,
+}
+
+impl ContactCache {
+    pub fn add(&mut self, contact: Contact) {
+        self.current.push(contact);
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.current
+    }
+
+    /// Forget every contact, without triggering `CollisionEnded`.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+}
+
+pub fn update_contacts(
+    mut commands: Commands,
+    mut cache: ResMut<ContactCache>,
+    physic_objects: Query<Option<&PhysicObject>>,
+) {
+    let ContactCache { previous, current } = &mut *cache;
+
+    // Objects that are both asleep aren't checked anymore but are still touching.
+    // Obstacles have no `PhysicObject` and never move.
+    let is_resting = |entity| {
+        physic_objects
+            .get(entity)
+            .is_ok_and(|object| object.is_none_or(|object| !object.is_awake()))
+    };
+
+    for contact in current.iter() {
+        let targets = [contact.entity1, contact.entity2];
+        if previous.iter().any(|other| other.is_same_pair(contact)) {
+            commands.trigger_targets(CollisionStay(*contact), targets);
+        } else {
+            commands.trigger_targets(CollisionStarted(*contact), targets);
+        }
+    }
+
+    for contact in previous.iter() {
+        if current.iter().any(|other| other.is_same_pair(contact)) {
+            continue;
+        }
+
+        // Nothing is left to tell about a despawned object
+        if !physic_objects.contains(contact.entity1) || !physic_objects.contains(contact.entity2) {
+            continue;
+        }
+
+        if is_resting(contact.entity1) && is_resting(contact.entity2) {
+            current.push(*contact);
+        } else {
+            let targets = [contact.entity1, contact.entity2];
+            commands.trigger_targets(CollisionEnded(*contact), targets);
+        }
+    }
+
+    core::mem::swap(previous, current);
+    current.clear();
+}
@@ -0,0 +1,193 @@
+use agb::display::{object::PaletteVram, palette16::Palette16};
+use alloc::vec::Vec;
+use bevy::prelude::*;
+
+use super::{
+    BoxCollider, CircleCollider, ObstacleShape, PhysicConfig, PhysicObject, PolygonCollider,
+    Scalar, SegmentCollider, Vector, to_vec2,
+};
+use crate::render::{DOT_CENTER, Sprite, SpriteHandles, dot_sprite};
+
+/// Draw the colliders, the velocities and the boundary with dots.
+///
+/// Hold L and R then press Select to show or hide it.
+pub struct PhysicsDebugPlugin;
+
+impl Plugin for PhysicsDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsDebug>();
+
+        app.add_systems(Startup, spawn_debug_dots);
+        app.add_systems(Update, toggle_physics_debug);
+        app.add_systems(
+            PostUpdate,
+            draw_physics_debug.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PhysicsDebug {
+    pub enabled: bool,
+}
+
+/// Number of objects the overlay can use. The rest of the 128 are kept for the game.
+const DEBUG_OAM_BUDGET: usize = 40;
+/// Part of the budget kept for the obstacles, so they are still drawn next to many balls.
+const OBSTACLE_DOTS: usize = 12;
+
+/// Cosine of an eighth of a turn.
+const DIAGONAL: Scalar = Scalar::from_raw(181);
+
+/// Where the velocity dots are drawn, in seconds of movement.
+const VELOCITY_DOTS: [Scalar; 2] = [Scalar::from_raw(32), Scalar::from_raw(64)];
+
+/// Dot of the overlay, with the colour its sprite currently has.
+#[derive(Component)]
+struct DebugDot(Option<DebugColour>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DebugColour {
+    Collider,
+    Velocity,
+    Boundary,
+}
+
+#[derive(Resource)]
+struct DebugSprites {
+    collider: Sprite,
+    velocity: Sprite,
+    boundary: Sprite,
+}
+
+impl DebugSprites {
+    fn get(&self, colour: DebugColour) -> &Sprite {
+        match colour {
+            DebugColour::Collider => &self.collider,
+            DebugColour::Velocity => &self.velocity,
+            DebugColour::Boundary => &self.boundary,
+        }
+    }
+}
+
+fn spawn_debug_dots(mut commands: Commands, mut handles: NonSendMut<SpriteHandles>) {
+    let mut palette = [0x0; 16];
+    palette[1] = 0x03E0; // Green
+    palette[2] = 0x001F; // Red
+    palette[3] = 0x7FFF; // White
+    let palette = PaletteVram::new(&Palette16::new(palette)).unwrap();
+
+    let mut dot = |colour: usize| dot_sprite(&mut handles, &palette, colour);
+
+    let sprites = DebugSprites {
+        collider: dot(1),
+        velocity: dot(2),
+        boundary: dot(3),
+    };
+
+    for _ in 0..DEBUG_OAM_BUDGET {
+        let mut sprite = sprites.boundary.clone();
+        sprite.visible = false;
+        commands.spawn((DebugDot(None), Transform::IDENTITY, sprite));
+    }
+
+    commands.insert_resource(sprites);
+}
+
+fn toggle_physics_debug(gamepad: Single<&Gamepad>, mut debug: ResMut<PhysicsDebug>) {
+    if gamepad.pressed(GamepadButton::LeftTrigger)
+        && gamepad.pressed(GamepadButton::RightTrigger)
+        && gamepad.just_pressed(GamepadButton::Select)
+    {
+        debug.enabled = !debug.enabled;
+    }
+}
+
+fn draw_physics_debug(
+    debug: Res<PhysicsDebug>,
+    config: Res<PhysicConfig>,
+    sprites: Res<DebugSprites>,
+    mut points: Local<Vec<(Vector, DebugColour)>>,
+    mut obstacle_points: Local<Vec<(Vector, DebugColour)>>,
+    mut dots: Query<(&mut DebugDot, &mut Transform, &mut Sprite)>,
+    circles: Query<(&PhysicObject, &CircleCollider)>,
+    boxes: Query<&BoxCollider>,
+    segments: Query<&SegmentCollider>,
+    polygons: Query<&PolygonCollider>,
+) {
+    points.clear();
+    obstacle_points.clear();
+
+    if debug.enabled {
+        config
+            .boundary
+            .for_each_outline_point(|point| points.push((point, DebugColour::Boundary)));
+
+        let zero = Scalar::new(0);
+        let one = Scalar::new(1);
+        let directions = [
+            Vector::new(one, zero),
+            Vector::new(DIAGONAL, DIAGONAL),
+            Vector::new(zero, one),
+            Vector::new(-DIAGONAL, DIAGONAL),
+            Vector::new(-one, zero),
+            Vector::new(-DIAGONAL, -DIAGONAL),
+            Vector::new(zero, -one),
+            Vector::new(DIAGONAL, -DIAGONAL),
+        ];
+
+        for (object, collider) in &circles {
+            let center = collider.center(object);
+
+            for direction in directions {
+                points.push((
+                    center + direction * collider.radius(),
+                    DebugColour::Collider,
+                ));
+            }
+
+            if object.velocity != Vector::default() {
+                for time in VELOCITY_DOTS {
+                    points.push((center + object.velocity * time, DebugColour::Velocity));
+                }
+            }
+        }
+
+        let mut outline = |shape: &dyn ObstacleShape| {
+            shape.for_each_edge(&mut |start, end| {
+                obstacle_points.push((start, DebugColour::Collider));
+                obstacle_points.push(((start + end) / 2, DebugColour::Collider));
+            });
+        };
+
+        for shape in &boxes {
+            outline(shape);
+        }
+        for shape in &segments {
+            outline(shape);
+        }
+        for shape in &polygons {
+            outline(shape);
+        }
+    }
+
+    // Points past the budget are dropped. The boundary and the balls come first, but leave
+    // enough dots for the obstacles.
+    let others = DEBUG_OAM_BUDGET - obstacle_points.len().min(OBSTACLE_DOTS);
+    let mut points = points.iter().take(others).chain(obstacle_points.iter());
+    for (mut dot, mut transform, mut sprite) in &mut dots {
+        let Some(&(point, colour)) = points.next() else {
+            if sprite.visible {
+                sprite.visible = false;
+            }
+            continue;
+        };
+
+        if dot.0 != Some(colour) {
+            *sprite = sprites.get(colour).clone();
+            dot.0 = Some(colour);
+        }
+        sprite.visible = true;
+        transform.translation = (to_vec2(point) - DOT_CENTER).extend(0.);
+    }
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+/// Which layers an object is part of and which layers it collides with.
+///
+/// Two objects only collide when each one is part of a layer the other one collides with.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub memberships: u16,
+    pub filters: u16,
+}
+
+impl CollisionLayers {
+    pub const ALL: u16 = u16::MAX;
+    pub const NONE: u16 = 0;
+
+    pub const fn new(memberships: u16, filters: u16) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    pub fn interacts_with(&self, other: &Self) -> bool {
+        self.memberships & other.filters != 0 && other.memberships & self.filters != 0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::new(Self::ALL, Self::ALL)
+    }
+}
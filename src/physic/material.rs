@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use super::Scalar;
+
+/// How the values of two materials touching each other are combined.
+///
+/// When both materials use a different rule, the one declared last wins.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum CombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub fn combine(self, other: Self, value: Scalar, other_value: Scalar) -> Scalar {
+        match self.max(other) {
+            Self::Average => (value + other_value) / 2,
+            Self::Min => value.min(other_value),
+            Self::Multiply => value * other_value,
+            Self::Max => value.max(other_value),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PhysicMaterial {
+    /// Part of the speed kept after a bounce. 0 stops dead and 1 is perfectly elastic.
+    pub restitution: Scalar,
+    /// Resistance to sliding along the contact.
+    pub friction: Scalar,
+    pub restitution_combine: CombineRule,
+    pub friction_combine: CombineRule,
+}
+
+impl PhysicMaterial {
+    pub fn combined_restitution(&self, other: &Self) -> Scalar {
+        self.restitution_combine.combine(
+            other.restitution_combine,
+            self.restitution,
+            other.restitution,
+        )
+    }
+
+    pub fn combined_friction(&self, other: &Self) -> Scalar {
+        self.friction_combine
+            .combine(other.friction_combine, self.friction, other.friction)
+    }
+}
+
+impl Default for PhysicMaterial {
+    fn default() -> Self {
+        Self {
+            restitution: Scalar::new(1),
+            friction: Scalar::new(0),
+            restitution_combine: CombineRule::Average,
+            friction_combine: CombineRule::Average,
+        }
+    }
+}
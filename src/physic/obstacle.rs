@@ -0,0 +1,314 @@
+use alloc::vec::Vec;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    CollisionLayers, PhysicMaterial, Scalar, Sensor, Vector, direction_and_length, perpendicular,
+};
+
+/// Shape of a static collider that circles bounce on.
+pub trait ObstacleShape {
+    /// Closest point of the outline of the shape and if `point` is inside the shape.
+    fn closest_point(&self, point: Vector) -> (Vector, bool);
+
+    /// Call `f` with the start and end of each side of the outline.
+    fn for_each_edge(&self, f: &mut dyn FnMut(Vector, Vector));
+}
+
+/// Axis aligned box, in world space.
+#[derive(Component, Clone, Copy)]
+#[require(PhysicMaterial, CollisionLayers)]
+pub struct BoxCollider {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl ObstacleShape for BoxCollider {
+    fn closest_point(&self, point: Vector) -> (Vector, bool) {
+        let clamped = Vector::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        );
+
+        if clamped != point {
+            return (clamped, false);
+        }
+
+        // Inside the box, the closest point is on the nearest side
+        let left = point.x - self.min.x;
+        let right = self.max.x - point.x;
+        let top = point.y - self.min.y;
+        let bottom = self.max.y - point.y;
+
+        let closest = if left.min(right) < top.min(bottom) {
+            if left < right {
+                Vector::new(self.min.x, point.y)
+            } else {
+                Vector::new(self.max.x, point.y)
+            }
+        } else if top < bottom {
+            Vector::new(point.x, self.min.y)
+        } else {
+            Vector::new(point.x, self.max.y)
+        };
+
+        (closest, true)
+    }
+
+    fn for_each_edge(&self, f: &mut dyn FnMut(Vector, Vector)) {
+        let top_right = Vector::new(self.max.x, self.min.y);
+        let bottom_left = Vector::new(self.min.x, self.max.y);
+
+        f(self.min, top_right);
+        f(top_right, self.max);
+        f(self.max, bottom_left);
+        f(bottom_left, self.min);
+    }
+}
+
+/// Thin wall between two points, in world space.
+#[derive(Component, Clone, Copy)]
+#[require(PhysicMaterial, CollisionLayers)]
+pub struct SegmentCollider {
+    pub start: Vector,
+    pub end: Vector,
+}
+
+fn closest_point_on_segment(start: Vector, end: Vector, point: Vector) -> Vector {
+    let direction = end - start;
+    let length_squared = direction.magnitude_squared();
+    if length_squared == Scalar::new(0) {
+        return start;
+    }
+
+    let t = ((point - start).dot(direction) / length_squared).clamp(Scalar::new(0), Scalar::new(1));
+    start + direction * t
+}
+
+impl ObstacleShape for SegmentCollider {
+    fn closest_point(&self, point: Vector) -> (Vector, bool) {
+        (closest_point_on_segment(self.start, self.end, point), false)
+    }
+
+    fn for_each_edge(&self, f: &mut dyn FnMut(Vector, Vector)) {
+        f(self.start, self.end);
+    }
+}
+
+/// Convex polygon, in world space. The points can be in either winding order.
+#[derive(Component, Clone)]
+#[require(PhysicMaterial, CollisionLayers)]
+pub struct PolygonCollider {
+    pub points: Vec<Vector>,
+}
+
+impl PolygonCollider {
+    pub fn new(points: impl IntoIterator<Item = Vector>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+        }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vector, Vector)> + '_ {
+        self.points
+            .iter()
+            .copied()
+            .zip(self.points.iter().copied().cycle().skip(1))
+    }
+}
+
+impl ObstacleShape for PolygonCollider {
+    fn closest_point(&self, point: Vector) -> (Vector, bool) {
+        let zero = Scalar::new(0);
+
+        let mut closest = None;
+        let mut closest_distance = zero;
+        let mut has_positive_side = false;
+        let mut has_negative_side = false;
+
+        for (start, end) in self.edges() {
+            let edge = end - start;
+            let to_point = point - start;
+            let side = edge.x * to_point.y - edge.y * to_point.x;
+            has_positive_side |= side > zero;
+            has_negative_side |= side < zero;
+
+            let candidate = closest_point_on_segment(start, end, point);
+            let distance = (point - candidate).magnitude_squared();
+            if closest.is_none() || distance < closest_distance {
+                closest = Some(candidate);
+                closest_distance = distance;
+            }
+        }
+
+        let inside = !(has_positive_side && has_negative_side);
+        (closest.unwrap_or(point), inside)
+    }
+
+    fn for_each_edge(&self, f: &mut dyn FnMut(Vector, Vector)) {
+        for (start, end) in self.edges() {
+            f(start, end);
+        }
+    }
+}
+
+/// Normal, pointing toward the circle, and penetration depth of a circle touching a shape.
+pub fn circle_contact(
+    shape: &(impl ObstacleShape + ?Sized),
+    center: Vector,
+    radius: Scalar,
+) -> Option<(Vector, Scalar)> {
+    let (closest, inside) = shape.closest_point(center);
+
+    let offset = if inside {
+        closest - center
+    } else {
+        center - closest
+    };
+
+    let (normal, distance) = direction_and_length(offset)?;
+    let penetration = if inside {
+        radius + distance
+    } else {
+        radius - distance
+    };
+
+    (penetration > Scalar::new(0)).then_some((normal, penetration))
+}
+
+/// Distance along `direction`, a unit vector, after which a ray from `origin` enters the
+/// circle.
+pub fn ray_circle(
+    origin: Vector,
+    direction: Vector,
+    center: Vector,
+    radius: Scalar,
+) -> Option<Scalar> {
+    let zero = Scalar::new(0);
+
+    let offset = origin - center;
+    let c = offset.magnitude_squared() - radius * radius;
+    if c < zero {
+        // Starting inside
+        return None;
+    }
+
+    let b = offset.dot(direction);
+    if b > zero {
+        return None;
+    }
+
+    let discriminant = b * b - c;
+    if discriminant < zero {
+        return None;
+    }
+
+    Some((-b - discriminant.sqrt()).max(zero))
+}
+
+/// Distance along `direction`, a unit vector, after which a circle moving from `origin`
+/// touches the segment, and the normal of the contact pointing toward the circle.
+fn cast_circle_against_segment(
+    origin: Vector,
+    radius: Scalar,
+    direction: Vector,
+    start: Vector,
+    end: Vector,
+) -> Option<(Scalar, Vector)> {
+    let zero = Scalar::new(0);
+    let edge = end - start;
+
+    let mut closest: Option<(Scalar, Vector)> = None;
+
+    if let Some((mut normal, _)) = direction_and_length(perpendicular(edge)) {
+        let mut side_distance = (origin - start).dot(normal);
+        if side_distance < zero {
+            normal = -normal;
+            side_distance = -side_distance;
+        }
+
+        let approach = direction.dot(normal);
+        if approach < zero && side_distance >= radius {
+            let distance = (side_distance - radius) / -approach;
+            let point = origin + direction * distance - normal * radius;
+            let along = (point - start).dot(edge);
+
+            if along >= zero && along <= edge.magnitude_squared() {
+                closest = Some((distance, normal));
+            }
+        }
+    }
+
+    // The ends of the segment are hit like circles of radius zero
+    for corner in [start, end] {
+        let Some(distance) = ray_circle(origin, direction, corner, radius) else {
+            continue;
+        };
+        if closest.is_some_and(|(closest, _)| closest <= distance) {
+            continue;
+        }
+
+        let offset = origin + direction * distance - corner;
+        let normal = direction_and_length(offset).map_or(-direction, |(normal, _)| normal);
+        closest = Some((distance, normal));
+    }
+
+    closest
+}
+
+/// Distance along `direction`, a unit vector, after which a circle moving from `origin`
+/// touches the shape, and the normal of the contact pointing toward the circle. `None`
+/// when the circle starts inside the shape.
+pub fn cast_circle(
+    shape: &(impl ObstacleShape + ?Sized),
+    origin: Vector,
+    radius: Scalar,
+    direction: Vector,
+) -> Option<(Scalar, Vector)> {
+    let (_, inside) = shape.closest_point(origin);
+    if inside {
+        return None;
+    }
+
+    let mut closest: Option<(Scalar, Vector)> = None;
+    shape.for_each_edge(&mut |start, end| {
+        if let Some(hit) = cast_circle_against_segment(origin, radius, direction, start, end)
+            && closest.is_none_or(|(closest, _)| hit.0 < closest)
+        {
+            closest = Some(hit);
+        }
+    });
+
+    closest
+}
+
+type ObstacleQuery<'w, 's, T> = Query<'w, 's, (&'static T, &'static CollisionLayers, Has<Sensor>)>;
+
+/// Every obstacle collider, whatever its shape.
+#[derive(SystemParam)]
+pub struct Obstacles<'w, 's> {
+    boxes: ObstacleQuery<'w, 's, BoxCollider>,
+    segments: ObstacleQuery<'w, 's, SegmentCollider>,
+    polygons: ObstacleQuery<'w, 's, PolygonCollider>,
+}
+
+impl Obstacles<'_, '_> {
+    /// Call `f` with the shape and the layers of every obstacle, except the sensors which
+    /// don't stop anything.
+    pub fn for_each(&self, mut f: impl FnMut(&dyn ObstacleShape, &CollisionLayers)) {
+        for (shape, layers, is_sensor) in &self.boxes {
+            if !is_sensor {
+                f(shape, layers);
+            }
+        }
+        for (shape, layers, is_sensor) in &self.segments {
+            if !is_sensor {
+                f(shape, layers);
+            }
+        }
+        for (shape, layers, is_sensor) in &self.polygons {
+            if !is_sensor {
+                f(shape, layers);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use alloc::vec::Vec;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    CircleCollider, CollisionLayers, PhysicConfig, PhysicMaterial, PhysicObject, PhysicStep,
+    PhysicZone, Scalar, ShapeHit, SpatialQuery, SpatialQueryFilter, Vector, ccd,
+    collide_with_boundary, direction_and_length, obstacle::Obstacles, zone::ZoneInfluence,
+};
+
+/// Path of a shot, from the center of the ball when it is shot.
+#[derive(Default, Debug)]
+pub struct PredictedPath {
+    /// Centers of the ball, one for each substep.
+    pub points: Vec<Vector>,
+    /// Center of the ball when it bounced on a wall for the first time.
+    pub bounce: Option<Vector>,
+    /// First collider hit. The path ends there.
+    pub hit: Option<ShapeHit>,
+}
+
+/// Predict where a ball goes by stepping a copy of it with the same code as the physic
+/// schedule: `move_physic_objects` for the movement and `keep_object_in_boundary` for the
+/// walls. Other colliders are found with a `SpatialQuery` and end the path.
+#[derive(SystemParam)]
+pub struct ShotPredictor<'w, 's> {
+    config: Res<'w, PhysicConfig>,
+    fixed_time: Res<'w, Time<Fixed>>,
+    balls: Query<
+        'w,
+        's,
+        (
+            &'static PhysicObject,
+            &'static CircleCollider,
+            &'static PhysicMaterial,
+            &'static CollisionLayers,
+        ),
+    >,
+    zones: Query<'w, 's, &'static PhysicZone>,
+    obstacles: Obstacles<'w, 's>,
+    spatial_query: SpatialQuery<'w, 's>,
+}
+
+impl ShotPredictor<'_, '_> {
+    /// Fill `path` with the next `duration` seconds of `ball` if it is shot from `center`
+    /// with `impulse`. The path stops after the second wall bounce, or when the ball falls
+    /// asleep or leaves the arena. Returns `false` when `ball` isn't a ball.
+    pub fn predict(
+        &self,
+        ball: Entity,
+        center: Vector,
+        impulse: Vector,
+        duration: Scalar,
+        path: &mut PredictedPath,
+    ) -> bool {
+        path.points.clear();
+        path.bounce = None;
+        path.hit = None;
+
+        let Ok((object, collider, material, layers)) = self.balls.get(ball) else {
+            return false;
+        };
+        let config = &*self.config;
+        let step = PhysicStep::new(self.fixed_time.timestep().as_secs_f32(), config);
+        let radius = collider.radius();
+        let filter = SpatialQueryFilter::from_layers(*layers).excluding(ball);
+
+        let mut object = PhysicObject {
+            enable: true,
+            position: collider.origin_for_center(center),
+            impulse,
+            velocity: Vector::default(),
+            angular_velocity: Scalar::new(0),
+            ..object.clone()
+        };
+        object.wake_up();
+
+        for _ in 0..step.count(duration) {
+            if !object.is_awake() {
+                break;
+            }
+
+            let center = collider.center(&object);
+            let zone = ZoneInfluence::at(center, &self.zones);
+            let mut displacement = object.integrate_velocity(&step, &zone, config.spin_curve);
+
+            if let Some((direction, travel)) = direction_and_length(displacement)
+                && let Some(hit) = self
+                    .spatial_query
+                    .cast_circle(center, radius, direction, travel, &filter)
+                && hit.entity.is_some()
+            {
+                let contact_center = center + direction * hit.distance;
+                path.points.push(contact_center);
+                path.hit = Some(hit);
+                break;
+            }
+
+            if object.ccd || ccd::needs_sweep(displacement, radius) {
+                displacement = ccd::sweep(
+                    ball,
+                    center,
+                    radius,
+                    layers,
+                    displacement,
+                    &[],
+                    &self.obstacles,
+                    &config.boundary,
+                );
+            }
+            object.advance(displacement, &step, config.sleep_steps);
+
+            let center = collider.center(&object);
+            if config.boundary.is_out(center, radius) {
+                path.points.push(center);
+                break;
+            }
+
+            if collide_with_boundary(&mut object, center, radius, material, config) {
+                if path.bounce.is_some() {
+                    path.points.push(center);
+                    break;
+                }
+                path.bounce = Some(center);
+            }
+
+            path.points.push(collider.center(&object));
+        }
+
+        true
+    }
+}
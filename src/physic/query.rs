@@ -0,0 +1,253 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    BoxCollider, CircleCollider, CollisionLayers, ObstacleShape, PhysicConfig, PhysicObject,
+    PolygonCollider, Scalar, SegmentCollider, Sensor, Vector, direction_and_length,
+    obstacle::{self, ray_circle},
+};
+
+/// Which colliders a `SpatialQuery` can hit.
+#[derive(Clone, Copy, Debug)]
+pub struct SpatialQueryFilter {
+    /// Only colliders interacting with these layers are hit.
+    pub layers: CollisionLayers,
+    /// Collider to ignore, usually the one doing the query.
+    pub exclude: Option<Entity>,
+    pub include_sensors: bool,
+}
+
+impl Default for SpatialQueryFilter {
+    fn default() -> Self {
+        Self {
+            layers: CollisionLayers::default(),
+            exclude: None,
+            include_sensors: false,
+        }
+    }
+}
+
+impl SpatialQueryFilter {
+    pub fn from_layers(layers: CollisionLayers) -> Self {
+        Self {
+            layers,
+            ..default()
+        }
+    }
+
+    pub fn excluding(mut self, entity: Entity) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
+
+    fn accepts(&self, entity: Entity, layers: &CollisionLayers, is_sensor: bool) -> bool {
+        self.exclude != Some(entity)
+            && (self.include_sensors || !is_sensor)
+            && self.layers.interacts_with(layers)
+    }
+}
+
+/// First thing hit by a ray or a shape cast.
+#[derive(Clone, Copy, Debug)]
+pub struct ShapeHit {
+    /// Collider hit, `None` for the boundary.
+    pub entity: Option<Entity>,
+    /// Distance traveled before the hit.
+    pub distance: Scalar,
+    /// Point of contact on the surface of the collider hit.
+    pub point: Vector,
+    /// Normal of the surface hit, pointing toward the cast.
+    pub normal: Vector,
+}
+
+type ObstacleQuery<'w, 's, T> =
+    Query<'w, 's, (Entity, &'static T, &'static CollisionLayers, Has<Sensor>)>;
+
+/// Ask the physic world what is at a point or along a path, without moving anything.
+///
+/// Uses the positions of the last physic step. Casts don't hit colliders they start in.
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+    config: Res<'w, PhysicConfig>,
+    circles: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static PhysicObject,
+            &'static CircleCollider,
+            &'static CollisionLayers,
+            Has<Sensor>,
+        ),
+    >,
+    boxes: ObstacleQuery<'w, 's, BoxCollider>,
+    segments: ObstacleQuery<'w, 's, SegmentCollider>,
+    polygons: ObstacleQuery<'w, 's, PolygonCollider>,
+}
+
+impl SpatialQuery<'_, '_> {
+    /// First collider or side of the boundary hit by a ray.
+    pub fn cast_ray(
+        &self,
+        origin: Vector,
+        direction: Vector,
+        max_distance: Scalar,
+        filter: &SpatialQueryFilter,
+    ) -> Option<ShapeHit> {
+        self.cast_circle(origin, Scalar::new(0), direction, max_distance, filter)
+    }
+
+    /// First collider or side of the boundary hit by a circle moving from `origin`.
+    pub fn cast_circle(
+        &self,
+        origin: Vector,
+        radius: Scalar,
+        direction: Vector,
+        max_distance: Scalar,
+        filter: &SpatialQueryFilter,
+    ) -> Option<ShapeHit> {
+        let (direction, _) = direction_and_length(direction)?;
+
+        let mut closest = cast_boundary(&self.config, origin, radius, direction);
+
+        let mut keep = |hit: Option<ShapeHit>| {
+            if let Some(hit) = hit
+                && hit.distance <= max_distance
+                && closest.is_none_or(|closest| hit.distance < closest.distance)
+            {
+                closest = Some(hit);
+            }
+        };
+
+        for (entity, object, collider, layers, is_sensor) in &self.circles {
+            if !object.enable || !filter.accepts(entity, layers, is_sensor) {
+                continue;
+            }
+
+            let center = collider.center(object);
+            keep(
+                cast_against_circle(origin, radius, direction, center, collider.radius()).map(
+                    |hit| ShapeHit {
+                        entity: Some(entity),
+                        ..hit
+                    },
+                ),
+            );
+        }
+
+        let mut cast_obstacle = |entity: Entity, shape: &dyn ObstacleShape| {
+            keep(obstacle::cast_circle(shape, origin, radius, direction).map(
+                |(distance, normal)| ShapeHit {
+                    entity: Some(entity),
+                    distance,
+                    point: origin + direction * distance - normal * radius,
+                    normal,
+                },
+            ));
+        };
+
+        for (entity, shape, layers, is_sensor) in &self.boxes {
+            if filter.accepts(entity, layers, is_sensor) {
+                cast_obstacle(entity, shape);
+            }
+        }
+
+        for (entity, shape, layers, is_sensor) in &self.segments {
+            if filter.accepts(entity, layers, is_sensor) {
+                cast_obstacle(entity, shape);
+            }
+        }
+
+        for (entity, shape, layers, is_sensor) in &self.polygons {
+            if filter.accepts(entity, layers, is_sensor) {
+                cast_obstacle(entity, shape);
+            }
+        }
+
+        closest.filter(|hit| hit.distance <= max_distance)
+    }
+
+    /// Colliders containing `point`.
+    pub fn point_overlaps(
+        &self,
+        point: Vector,
+        filter: &SpatialQueryFilter,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let filter = *filter;
+
+        let circles = self
+            .circles
+            .iter()
+            .filter(move |(entity, object, collider, layers, is_sensor)| {
+                let radius = collider.radius();
+                object.enable
+                    && filter.accepts(*entity, layers, *is_sensor)
+                    && (point - collider.center(object)).magnitude_squared() <= radius * radius
+            })
+            .map(|(entity, ..)| entity);
+
+        let contains = move |entity: Entity,
+                             shape: &dyn ObstacleShape,
+                             layers: &CollisionLayers,
+                             is_sensor: bool| {
+            filter.accepts(entity, layers, is_sensor) && shape.closest_point(point).1
+        };
+
+        let boxes = self
+            .boxes
+            .iter()
+            .filter(move |(entity, shape, layers, is_sensor)| {
+                contains(*entity, *shape, layers, *is_sensor)
+            })
+            .map(|(entity, ..)| entity);
+
+        let polygons = self
+            .polygons
+            .iter()
+            .filter(move |(entity, shape, layers, is_sensor)| {
+                contains(*entity, *shape, layers, *is_sensor)
+            })
+            .map(|(entity, ..)| entity);
+
+        // Segments have no inside, so they never contain a point
+        circles.chain(boxes).chain(polygons)
+    }
+}
+
+fn cast_against_circle(
+    origin: Vector,
+    radius: Scalar,
+    direction: Vector,
+    center: Vector,
+    other_radius: Scalar,
+) -> Option<ShapeHit> {
+    let distance = ray_circle(origin, direction, center, radius + other_radius)?;
+    let hit_center = origin + direction * distance;
+
+    let offset = hit_center - center;
+    let normal = direction_and_length(offset).map_or(-direction, |(normal, _)| normal);
+
+    Some(ShapeHit {
+        entity: None,
+        distance,
+        point: center + normal * other_radius,
+        normal,
+    })
+}
+
+/// Distance after which a circle moving from `origin` touches a side of the boundary.
+fn cast_boundary(
+    config: &PhysicConfig,
+    origin: Vector,
+    radius: Scalar,
+    direction: Vector,
+) -> Option<ShapeHit> {
+    // With a unit direction, the fraction of the displacement is the distance
+    let (distance, normal) = config.boundary.time_of_impact(origin, radius, direction)?;
+
+    Some(ShapeHit {
+        entity: None,
+        distance,
+        point: origin + direction * distance - normal * radius,
+        normal,
+    })
+}
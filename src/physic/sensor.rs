@@ -0,0 +1,86 @@
+use alloc::vec::Vec;
+use bevy::prelude::*;
+
+use super::PhysicObject;
+
+/// Collider reporting overlaps with `SensorEnter` and `SensorExit` without being pushed
+/// or pushing anything.
+///
+/// Works with a `CircleCollider` or with any obstacle collider.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Sensor;
+
+/// An object overlapping a sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SensorOverlap {
+    pub sensor: Entity,
+    pub entity: Entity,
+}
+
+/// Triggered on the sensor and the object the first frame they overlap.
+#[derive(Event, Clone, Copy, Debug, Deref)]
+pub struct SensorEnter(pub SensorOverlap);
+
+/// Triggered on the sensor and the object the first frame they stop overlapping.
+#[derive(Event, Clone, Copy, Debug, Deref)]
+pub struct SensorExit(pub SensorOverlap);
+
+/// Overlaps of the current and previous frame, used to know when objects enter and exit.
+#[derive(Resource, Default)]
+pub struct SensorCache {
+    previous: Vec<SensorOverlap>,
+    current: Vec<SensorOverlap>,
+}
+
+impl SensorCache {
+    pub fn add(&mut self, sensor: Entity, entity: Entity) {
+        self.current.push(SensorOverlap { sensor, entity });
+    }
+
+    pub fn overlaps(&self) -> &[SensorOverlap] {
+        &self.current
+    }
+
+    /// Forget every overlap, without triggering `SensorExit`.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+}
+
+pub fn update_sensors(
+    mut commands: Commands,
+    mut cache: ResMut<SensorCache>,
+    physic_objects: Query<Option<&PhysicObject>>,
+) {
+    let SensorCache { previous, current } = &mut *cache;
+
+    // Objects resting in a sensor aren't checked anymore but are still inside it.
+    // Obstacle sensors have no `PhysicObject` and never move.
+    let is_resting = |entity| {
+        physic_objects
+            .get(entity)
+            .is_ok_and(|object| object.is_none_or(|object| !object.is_awake()))
+    };
+
+    for overlap in current.iter() {
+        if !previous.contains(overlap) {
+            commands.trigger_targets(SensorEnter(*overlap), [overlap.sensor, overlap.entity]);
+        }
+    }
+
+    for overlap in previous.iter() {
+        if current.contains(overlap) {
+            continue;
+        }
+
+        if is_resting(overlap.sensor) && is_resting(overlap.entity) {
+            current.push(*overlap);
+        } else {
+            commands.trigger_targets(SensorExit(*overlap), [overlap.sensor, overlap.entity]);
+        }
+    }
+
+    core::mem::swap(previous, current);
+    current.clear();
+}
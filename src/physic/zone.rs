@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use super::{Scalar, Vector, direction_and_length};
+
+/// Region of the level changing how the objects inside it move.
+///
+/// An object is inside the zone when its center is. Overlapping zones add their forces
+/// and multiply their drag.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PhysicZone {
+    pub shape: PhysicZoneShape,
+    pub effect: PhysicZoneEffect,
+}
+
+/// Area of a `PhysicZone`, in world space.
+#[derive(Clone, Copy, Debug)]
+pub enum PhysicZoneShape {
+    Rect { min: Vector, max: Vector },
+    Circle { center: Vector, radius: Scalar },
+}
+
+impl PhysicZoneShape {
+    pub fn contains(&self, point: Vector) -> bool {
+        match *self {
+            Self::Rect { min, max } => {
+                point.x >= min.x && point.x <= max.x && point.y >= min.y && point.y <= max.y
+            }
+            Self::Circle { center, radius } => {
+                (point - center).magnitude_squared() <= radius * radius
+            }
+        }
+    }
+
+    /// Point where the gravity wells pull toward.
+    fn center(&self) -> Vector {
+        match *self {
+            Self::Rect { min, max } => (min + max) / 2,
+            Self::Circle { center, .. } => center,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PhysicZoneEffect {
+    /// Accelerate the objects toward the center of the zone, or away from it when negative.
+    GravityWell(Scalar),
+    /// Constant acceleration, like wind blowing.
+    Force(Vector),
+    /// Moving floor. The drag slow the objects down relative to it instead of to the level,
+    /// so they end up carried at its speed.
+    Conveyor(Vector),
+    /// Multiply the drag of the objects, lower than one for ice and higher for mud.
+    Drag(Scalar),
+}
+
+/// Sum of the effects of all the zones an object is in.
+pub struct ZoneInfluence {
+    pub acceleration: Vector,
+    pub surface_velocity: Vector,
+    pub drag_factor: Scalar,
+}
+
+impl Default for ZoneInfluence {
+    fn default() -> Self {
+        Self {
+            acceleration: Vector::default(),
+            surface_velocity: Vector::default(),
+            drag_factor: Scalar::new(1),
+        }
+    }
+}
+
+impl ZoneInfluence {
+    pub fn at<'a>(point: Vector, zones: impl IntoIterator<Item = &'a PhysicZone>) -> Self {
+        let mut influence = Self::default();
+
+        for zone in zones {
+            if !zone.shape.contains(point) {
+                continue;
+            }
+
+            match zone.effect {
+                PhysicZoneEffect::GravityWell(strength) => {
+                    let offset = zone.shape.center() - point;
+                    if let Some((direction, _)) = direction_and_length(offset) {
+                        influence.acceleration += direction * strength;
+                    }
+                }
+                PhysicZoneEffect::Force(acceleration) => influence.acceleration += acceleration,
+                PhysicZoneEffect::Conveyor(velocity) => influence.surface_velocity += velocity,
+                PhysicZoneEffect::Drag(factor) => influence.drag_factor *= factor,
+            }
+        }
+
+        influence
+    }
+}
@@ -1,9 +1,10 @@
 use agb::{
     display::{
+        Priority,
         affine::{AffineMatrix, AffineMatrixObject},
-        object::{AffineMatrixInstance, AffineMode},
+        object::{AffineMatrixInstance, AffineMode, DynamicSprite, PaletteVram, Size},
     },
-    fixnum::Num,
+    fixnum::{Num, Vector2D},
 };
 use alloc::{borrow::ToOwned, boxed::Box};
 use bevy::{
@@ -13,12 +14,13 @@ use bevy::{
         error::Result,
         system::{IntoSystem, NonSendMut, Query, RunSystemOnce, System},
     },
-    math::Vec3,
+    math::{Vec2, Vec3},
     transform::components::{GlobalTransform, Transform},
 };
 pub use bevy_mod_gba::{Sprite, SpriteHandles};
 use log::warn;
 
+pub use crate::physic::SPRITE_ANCHOR;
 use crate::text::{TextQuery, render_text_object};
 
 pub struct RenderPlugin;
@@ -60,6 +62,22 @@ impl Plugin for RenderPlugin {
 // #[derive(Resource, Deref, DerefMut)]
 // pub struct BlendDist(agb::display::BlendDist);
 
+/// Center of the dot drawn by `dot_sprite`, from the top-left corner of the sprite.
+pub const DOT_CENTER: Vec2 = Vec2::new(1., 1.);
+
+/// Sprite with a 2x2 dot of `colour` from `palette` in its top-left corner, to draw dotted
+/// lines.
+pub fn dot_sprite(handles: &mut SpriteHandles, palette: &PaletteVram, colour: usize) -> Sprite {
+    let mut sprite = DynamicSprite::new(Size::S8x8);
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        sprite.set_pixel(x, y, colour);
+    }
+
+    let mut sprite = Sprite::new(handles.add(sprite.to_vram(palette.clone())));
+    sprite.priority = Priority::P0;
+    sprite
+}
+
 #[derive(Component)]
 #[require(Transform)]
 pub struct AffineSprite {
@@ -146,18 +164,21 @@ pub fn render_objects_and_text(
 
                 if let Some(affine) = affine {
                     if affine.enable {
-                        let (_scale, rotation, _translation) =
+                        let (scale, rotation, _translation) =
                             transform.to_scale_rotation_translation();
 
-                        // let adjusted_scale: Vector2D<Num<i32, 8>> =
-                        //     Vector2D::new(Num::from_f32(scale.x), Num::from_f32(scale.y));
+                        // The matrix maps screen pixels to sprite pixels, so it scales by the
+                        // inverse of the scale the sprite is drawn at.
+                        let inverse_scale = Vector2D::new(
+                            AffineMatrixElement::from_f32(1.0 / scale.x),
+                            AffineMatrixElement::from_f32(1.0 / scale.y),
+                        );
                         let affine_matrix =
                             AffineMatrix::from_rotation(AffineMatrixElement::from_f32(
                                 rotation.to_euler(bevy::math::EulerRot::XYZ).2,
-                            ));
-                        //    * AffineMatrix::from_scale(adjusted_scale);
+                            )) * AffineMatrix::from_scale(inverse_scale);
 
-                        // info!("scale {scale:?} rotation {rotation:?} {adjusted_scale:?} {affine_matrix:?}");
+                        // info!("scale {scale:?} rotation {rotation:?} {inverse_scale:?} {affine_matrix:?}");
 
                         let amo = AffineMatrixObject::try_from(affine_matrix)
                             .map_err(|_| "Cannot convert matrix")?;
@@ -0,0 +1,241 @@
+use alloc::vec::Vec;
+use bevy::{
+    app::TaskPoolPlugin,
+    ecs::system::RunSystemOnce,
+    prelude::*,
+    time::{TimePlugin, TimeUpdateStrategy},
+};
+
+use crate::{
+    ability::{Ability, AbilityPlugin, UseAbility},
+    ball_type::{BallPlugin, EnemyBall, Life, Team},
+    enemy_behavior::EnemyBehaviorPlugin,
+    launcher,
+    level::LevelData,
+    on_death::{OnDeathPlugin, Pickup},
+    physic::{
+        CircleCollider, PhysicConfig, PhysicObject, PhysicPlugin, PhysicStatus, PredictedPath,
+        Scalar, ShotPredictor, Vector, from_vec2,
+    },
+};
+
+/// Headless `App` playing a level with the real physic and ball gameplay, without
+/// rendering, sound or input. Every `step` is exactly one `FixedUpdate`.
+pub struct Simulation {
+    pub app: App,
+    level: &'static LevelData,
+    next_player_ball: usize,
+}
+
+impl Simulation {
+    pub fn new(level: &'static LevelData) -> Self {
+        let mut app = App::new();
+
+        app.add_plugins((
+            TaskPoolPlugin::default(),
+            TimePlugin,
+            TransformPlugin,
+            PhysicPlugin,
+            BallPlugin,
+            AbilityPlugin,
+            EnemyBehaviorPlugin,
+            OnDeathPlugin,
+        ));
+
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ));
+        app.insert_resource(PhysicConfig {
+            boundary: level.arena.boundary(),
+            ..default()
+        });
+        app.insert_resource(level.launcher_center());
+
+        let world = app.world_mut();
+        let mut commands = world.commands();
+        level.spawn_obstacles(&mut commands);
+        level.spawn_zones(&mut commands);
+        level.spawn_enemies_ball(&mut commands);
+        world.flush();
+
+        // The first update only starts the clock
+        app.update();
+
+        Self {
+            app,
+            level,
+            next_player_ball: 0,
+        }
+    }
+
+    /// Controller turned by `rotation` turns from its starting direction.
+    fn controller(&self, rotation: f32) -> Transform {
+        let mut controller = self.level.controller_transform();
+
+        let mut turn = controller.rotation.to_euler(EulerRot::XYZ).2 + rotation;
+        if turn > 0.5 {
+            turn -= 1.0;
+        } else if turn < -0.5 {
+            turn += 1.0;
+        }
+        controller.rotation = Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, turn);
+        controller
+    }
+
+    fn spawn_next_ball(&mut self, controller: &Transform, impulse_ratio: f32) -> Option<Entity> {
+        let world = self.app.world_mut();
+        let ball = self.level.spawn_player_ball(
+            &mut world.commands(),
+            self.next_player_ball,
+            launcher::ball_center(controller, impulse_ratio),
+        )?;
+        world.flush();
+
+        Some(ball)
+    }
+
+    fn launch_impulse(&self, ball: Entity, controller: &Transform, impulse_ratio: f32) -> Vector {
+        let Some(Team::Player(player_ball)) = self.app.world().get::<Team>(ball) else {
+            return Vector::default();
+        };
+
+        player_ball.launch_impulse(launcher::impulse(controller, impulse_ratio))
+    }
+
+    /// Path shown to the player for the next ball while aiming like `shoot`. Empty when
+    /// every ball of the level has been shot.
+    pub fn predict(
+        &mut self,
+        rotation: f32,
+        impulse_ratio: f32,
+        duration: Scalar,
+    ) -> PredictedPath {
+        let controller = self.controller(rotation);
+        let Some(ball) = self.spawn_next_ball(&controller, impulse_ratio) else {
+            return PredictedPath::default();
+        };
+
+        let center = from_vec2(launcher::ball_center(&controller, impulse_ratio).truncate());
+        let impulse = self.launch_impulse(ball, &controller, impulse_ratio);
+
+        let world = self.app.world_mut();
+        let path = world
+            .run_system_once(move |predictor: ShotPredictor| {
+                let mut path = PredictedPath::default();
+                predictor.predict(ball, center, impulse, duration, &mut path);
+                path
+            })
+            .unwrap();
+        world.despawn(ball);
+
+        path
+    }
+
+    /// Shoot the next player ball, like the player would with the controller turned by
+    /// `rotation` turns from its starting direction and the button held for `impulse_ratio`,
+    /// from 0 to 1. `None` when every ball of the level has been shot.
+    pub fn shoot(&mut self, rotation: f32, impulse_ratio: f32) -> Option<Entity> {
+        let controller = self.controller(rotation);
+        let ball = self.spawn_next_ball(&controller, impulse_ratio)?;
+        self.next_player_ball += 1;
+
+        let impulse = self.launch_impulse(ball, &controller, impulse_ratio);
+        let mut physic_object = self.app.world_mut().get_mut::<PhysicObject>(ball)?;
+        physic_object.impulse = impulse;
+        physic_object.enable = true;
+
+        Some(ball)
+    }
+
+    /// Use the ability of a ball, like pressing A again after shooting it.
+    pub fn use_ability(&mut self, ball: Entity) {
+        let world = self.app.world_mut();
+        world.trigger_targets(UseAbility, ball);
+        world.flush();
+    }
+
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    /// Step until nothing moves anymore, for at most `max_steps`. Returns whether the world
+    /// came to rest.
+    pub fn run_until_rest(&mut self, max_steps: usize) -> bool {
+        for _ in 0..max_steps {
+            self.step();
+            if self.is_at_rest() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn physic_object(&self, ball: Entity) -> Option<&PhysicObject> {
+        self.app.world().get::<PhysicObject>(ball)
+    }
+
+    pub fn ability(&self, ball: Entity) -> Option<&Ability> {
+        self.app.world().get::<Ability>(ball)
+    }
+
+    pub fn player_balls(&mut self) -> usize {
+        self.app
+            .world_mut()
+            .query::<&Team>()
+            .iter(self.app.world())
+            .filter(|team| matches!(team, Team::Player(_)))
+            .count()
+    }
+
+    /// First enemy of this kind still in the level.
+    pub fn enemy(&mut self, kind: EnemyBall) -> Option<Entity> {
+        self.app
+            .world_mut()
+            .query::<(Entity, &Team)>()
+            .iter(self.app.world())
+            .find(|(_, team)| **team == Team::Enemy(kind))
+            .map(|(entity, _)| entity)
+    }
+
+    /// Bring the life of a ball down to zero, it dies on the next step.
+    pub fn kill(&mut self, ball: Entity) {
+        self.app.world_mut().entity_mut(ball).insert(Life(0));
+    }
+
+    pub fn pickups(&mut self) -> Vec<Entity> {
+        self.app
+            .world_mut()
+            .query_filtered::<Entity, With<Pickup>>()
+            .iter(self.app.world())
+            .collect()
+    }
+
+    /// Center of the collider of a ball.
+    pub fn ball_center(&self, ball: Entity) -> Option<Vector> {
+        let world = self.app.world();
+        let collider = world.get::<CircleCollider>(ball)?;
+        Some(collider.center(world.get::<PhysicObject>(ball)?))
+    }
+
+    pub fn is_at_rest(&self) -> bool {
+        self.app
+            .world()
+            .resource::<PhysicStatus>()
+            .is_world_at_rest()
+    }
+
+    pub fn enemies_left(&mut self) -> usize {
+        self.app
+            .world_mut()
+            .query::<&Team>()
+            .iter(self.app.world())
+            .filter(|team| team.is_enemy())
+            .count()
+    }
+
+    /// Every enemy is gone, the level is won.
+    pub fn is_won(&mut self) -> bool {
+        self.enemies_left() == 0
+    }
+}
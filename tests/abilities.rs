@@ -0,0 +1,83 @@
+//! Mid-flight abilities of the player balls, used headless on the host.
+//!
+//! Run with `cargo test-host`.
+
+use lib::{ability::AbilityKind, level::LEVELS, physic::Scalar, simulation::Simulation};
+
+/// Long enough for any shot to come to rest, 30 seconds at 64 steps per second.
+const MAX_STEPS: usize = 64 * 30;
+
+/// "It is on the side", shooting the Boy, then the Dog, then the Princess.
+const SIDE_LEVEL: usize = 0;
+
+fn step_for(simulation: &mut Simulation, steps: u8) {
+    for _ in 0..steps {
+        simulation.step();
+    }
+}
+
+#[test]
+fn boy_splits_in_two() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+    let boy = simulation.shoot(0.0, 1.0).unwrap();
+    step_for(&mut simulation, AbilityKind::Split.cooldown());
+    simulation.use_ability(boy);
+
+    assert!(simulation.physic_object(boy).is_none());
+    assert_eq!(simulation.player_balls(), 2);
+    assert!(simulation.run_until_rest(MAX_STEPS));
+}
+
+#[test]
+fn ability_waits_for_its_cooldown_and_is_used_once() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+    let boy = simulation.shoot(0.0, 1.0).unwrap();
+    simulation.step();
+    simulation.use_ability(boy);
+    assert!(simulation.physic_object(boy).is_some());
+    assert!(!simulation.ability(boy).unwrap().used);
+
+    assert!(simulation.run_until_rest(MAX_STEPS));
+    let dog = simulation.shoot(0.0, 1.0).unwrap();
+    step_for(&mut simulation, AbilityKind::Dash.cooldown());
+    simulation.use_ability(dog);
+    assert!(simulation.ability(dog).unwrap().used);
+    simulation.step();
+    let speed = simulation.physic_object(dog).unwrap().velocity.magnitude();
+
+    // Already used, pressing again gives no impulse
+    let impulse = simulation.physic_object(dog).unwrap().impulse;
+    simulation.use_ability(dog);
+    assert_eq!(simulation.physic_object(dog).unwrap().impulse, impulse);
+    simulation.step();
+    let next_speed = simulation.physic_object(dog).unwrap().velocity.magnitude();
+    assert!(next_speed <= speed);
+}
+
+#[test]
+fn dog_dashes_along_its_velocity() {
+    let shoot_dog = |dash: bool| {
+        let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+        simulation.shoot(0.0, 1.0).unwrap();
+        assert!(simulation.run_until_rest(MAX_STEPS));
+
+        let dog = simulation.shoot(0.0, 1.0).unwrap();
+        step_for(&mut simulation, AbilityKind::Dash.cooldown());
+        if dash {
+            simulation.use_ability(dog);
+        }
+        simulation.step();
+
+        simulation.physic_object(dog).unwrap().velocity
+    };
+
+    let velocity = shoot_dog(false);
+    let dash_velocity = shoot_dog(true);
+
+    assert!(dash_velocity.magnitude() > velocity.magnitude() + Scalar::new(60));
+    // Same direction
+    assert!(dash_velocity.dot(velocity) > Scalar::new(0));
+}
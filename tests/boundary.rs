@@ -0,0 +1,32 @@
+//! Arena walls, checked without running a level.
+//!
+//! Run with `cargo test-host`.
+
+use lib::physic::{Boundary, Scalar, Vector};
+
+fn point(x: i32, y: i32) -> Vector {
+    Vector::new(Scalar::new(x), Scalar::new(y))
+}
+
+/// Right triangle, the center of its bounding box is on its long side.
+fn triangle() -> Boundary {
+    Boundary::polygon([point(20, 20), point(140, 140), point(140, 20)])
+}
+
+#[test]
+fn circle_inside_a_triangle_arena_is_not_out() {
+    assert!(!triangle().is_out(point(110, 50), Scalar::new(4)));
+}
+
+#[test]
+fn triangle_arena_pushes_circles_back_inside() {
+    let mut contacts = 0;
+
+    // Going through the long side, next to the center of the bounding box
+    triangle().for_each_contact(point(83, 77), Scalar::new(8), |normal, _| {
+        contacts += 1;
+        assert!(normal.x > Scalar::new(0) && normal.y < Scalar::new(0));
+    });
+
+    assert_eq!(contacts, 1);
+}
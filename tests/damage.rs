@@ -0,0 +1,48 @@
+//! Damage dealt by player balls hitting enemies.
+//!
+//! Run with `cargo test-host`.
+
+use lib::{
+    ball_type::{EnemyBall, Hit},
+    physic::{Scalar, Vector},
+};
+
+fn hit(impact_speed: i32, direction: Vector) -> Hit {
+    Hit {
+        impact_speed: Scalar::new(impact_speed),
+        weight: Scalar::new(1),
+        direction,
+    }
+}
+
+fn side() -> Vector {
+    Vector::new(Scalar::new(1), Scalar::new(0))
+}
+
+#[test]
+fn gentle_bump_does_nothing() {
+    assert_eq!(EnemyBall::GreenBlob.damage(&hit(10, side())), 0);
+}
+
+#[test]
+fn any_hard_enough_hit_hurts() {
+    assert_eq!(EnemyBall::GreenBlob.damage(&hit(35, side())), 1);
+}
+
+#[test]
+fn slam_hurts_more_than_a_bump() {
+    let bump = EnemyBall::Ghost.damage(&hit(60, side()));
+    let slam = EnemyBall::Ghost.damage(&hit(190, side()));
+
+    // The armor of the ghost absorbs the bump
+    assert_eq!(bump, 0);
+    assert_eq!(slam, 2);
+}
+
+#[test]
+fn weak_point_multiplies_damage() {
+    let top = Vector::new(Scalar::new(0), Scalar::new(-1));
+
+    assert_eq!(EnemyBall::Snake.damage(&hit(100, side())), 2);
+    assert_eq!(EnemyBall::Snake.damage(&hit(100, top)), 4);
+}
@@ -0,0 +1,149 @@
+//! Levels played headless on the host with the real physic and ball gameplay.
+//!
+//! Run with `cargo test-host`.
+
+use lib::{
+    ball_type::EnemyBall,
+    level::LEVELS,
+    physic::{PhysicConfig, PhysicObject, Scalar},
+    simulation::Simulation,
+};
+
+/// Long enough for any shot to come to rest, 30 seconds at 64 steps per second.
+const MAX_STEPS: usize = 64 * 30;
+
+/// "It is on the side": the tree hides the slime from a straight shot.
+const SIDE_LEVEL: usize = 0;
+
+/// "Hold the button!": a lone snake far from the launcher.
+const SNAKE_LEVEL: usize = 1;
+
+#[test]
+fn side_level_is_solvable_with_one_shot() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+    // Turned a bit more than a tenth of a turn toward the slime, at full power
+    simulation.shoot(-0.109, 1.0).unwrap();
+
+    assert!(simulation.run_until_rest(MAX_STEPS));
+    assert!(simulation.is_won());
+}
+
+#[test]
+fn side_level_straight_shot_hits_the_tree() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+    simulation.shoot(0.0, 1.0).unwrap();
+
+    assert!(simulation.run_until_rest(MAX_STEPS));
+    assert_eq!(simulation.enemies_left(), 1);
+}
+
+#[test]
+fn slime_bumping_into_the_tree_gets_hurt() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+    let slime = simulation.enemy(EnemyBall::GreenBlob).unwrap();
+    let tree = simulation.enemy(EnemyBall::Tree).unwrap();
+
+    // Thrown at the tree, like after a hit from a player ball
+    let toward_tree =
+        simulation.ball_center(tree).unwrap() - simulation.ball_center(slime).unwrap();
+    let world = simulation.app.world_mut();
+    let mut slime_object = world.get_mut::<PhysicObject>(slime).unwrap();
+    slime_object.impulse = toward_tree * 3 * slime_object.mass;
+
+    assert!(simulation.run_until_rest(MAX_STEPS));
+    assert!(simulation.is_won());
+}
+
+#[test]
+fn predicted_path_follows_the_shot() {
+    let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+
+    let path = simulation.predict(0.0, 1.0, Scalar::new(1));
+    assert!(path.hit.is_some(), "the straight shot should hit the tree");
+
+    let ball = simulation.shoot(0.0, 1.0).unwrap();
+
+    // The last point is where the ball touches the tree, not the end of a step
+    let (_contact, steps) = path.points.split_last().unwrap();
+    for point in steps {
+        simulation.step();
+        assert_eq!(simulation.ball_center(ball), Some(*point));
+    }
+}
+
+#[test]
+fn substeps_dont_change_how_far_a_shot_goes() {
+    let travel = |substeps| {
+        let mut simulation = Simulation::new(&LEVELS[SIDE_LEVEL]);
+        let world = simulation.app.world_mut();
+        world.resource_mut::<PhysicConfig>().substeps = substeps;
+
+        let ball = simulation.shoot(0.0, 1.0).unwrap();
+        let start = simulation.ball_center(ball).unwrap();
+        for _ in 0..4 {
+            simulation.step();
+        }
+        (simulation.ball_center(ball).unwrap() - start).magnitude()
+    };
+
+    let single = travel(1);
+    assert!(single > Scalar::new(4));
+    for substeps in [3, 5] {
+        assert!((travel(substeps) - single).abs() <= Scalar::new(1));
+    }
+}
+
+#[test]
+fn snake_slithers_only_while_a_shot_is_in_play() {
+    let mut simulation = Simulation::new(&LEVELS[SNAKE_LEVEL]);
+    let snake = simulation.enemy(EnemyBall::Snake).unwrap();
+    let start = simulation.ball_center(snake);
+
+    for _ in 0..64 {
+        simulation.step();
+    }
+    assert_eq!(simulation.ball_center(snake), start);
+
+    simulation.shoot(0.0, 0.0).unwrap();
+    for _ in 0..16 {
+        simulation.step();
+    }
+    assert_ne!(simulation.ball_center(snake), start);
+
+    assert!(simulation.run_until_rest(MAX_STEPS));
+}
+
+#[test]
+fn snake_drops_a_pickup_where_it_dies() {
+    let mut simulation = Simulation::new(&LEVELS[SNAKE_LEVEL]);
+    let snake = simulation.enemy(EnemyBall::Snake).unwrap();
+    let center = simulation.ball_center(snake);
+
+    simulation.kill(snake);
+    simulation.step();
+    assert!(simulation.is_won());
+
+    let pickups = simulation.pickups();
+    assert_eq!(pickups.len(), 1);
+    assert_eq!(simulation.ball_center(pickups[0]), center);
+}
+
+#[test]
+fn every_level_comes_to_rest() {
+    for level in LEVELS {
+        let mut simulation = Simulation::new(level);
+
+        for _ in level.player_balls {
+            simulation.shoot(0.0, 1.0).unwrap();
+            assert!(
+                simulation.run_until_rest(MAX_STEPS),
+                "{} never came to rest",
+                level.title
+            );
+        }
+
+        assert!(simulation.shoot(0.0, 1.0).is_none());
+    }
+}